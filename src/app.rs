@@ -3,7 +3,12 @@
 use eframe::egui;
 use eframe::egui::CollapsingHeader;
 use eframe::egui::ScrollArea;
+use std::collections::BTreeSet;
+use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::sync::mpsc;
 use std::sync::mpsc::Receiver;
 use std::sync::mpsc::Sender;
@@ -12,16 +17,67 @@ use tokio::runtime::Handle;
 use tracing::info;
 use tracing::warn;
 
+use crate::attachment::AttachmentsState;
+use crate::attachment::draw_attachment;
+use crate::chart_export::ExportFile;
+use crate::charts::ChartJob;
 use crate::charts::ChartState;
 use crate::charts::ChartType;
+use crate::charts::ProcessedChartData;
+use crate::comparison::ComparedFile;
+use crate::comparison::ComparisonState;
+use crate::cost::CostState;
+use crate::cost::PriceTable;
+use crate::cost::TokenizerKind;
+use crate::cost::draw_price_table;
+use crate::dump::DumpPart;
+use crate::filter::DrillDown;
+use crate::filter::FilteredView;
+use crate::filter::ThreadFilter;
+use crate::latency::LatencyState;
+use crate::model_params::ReasoningEffort;
+use crate::model_params::reasoning_effort;
+use crate::query::QueryJob;
+use crate::query::QueryOutcome;
+use crate::query::QueryState;
+use crate::reliability::ReliabilityState;
+use crate::sqlite;
+use crate::stats::StatsState;
+use crate::summary::SummaryState;
 use crate::t3_json::T3Json; // Import ChartState and ChartType
+use crate::t3_timestamp::TimestampForm;
+use crate::t3_timestamp::serialize_as;
+use crate::tabular::TableFormat;
+use crate::tabular::export_tables;
+use crate::timezone::DisplayTimezone;
 
-fn thread_url(thread_id: &str) -> String {
+pub(crate) fn thread_url(thread_id: &str) -> String {
     format!("https://t3.chat/chat/{thread_id}")
 }
 
+// Messages are moved straight out of the channel, so the variant size difference is fine.
+#[allow(clippy::large_enum_variant)]
 pub enum UiBoundMessage {
     ContentLoaded(MyDroppedFile),
+    PricesLoaded(PriceTable),
+    /// Chart data computed in the background, routed back by `ChartJob::state_id`.
+    ChartProcessed(ChartJob, ProcessedChartData),
+    /// A SQL console query run in the background.
+    QueryFinished(QueryOutcome),
+}
+
+/// Settings shared by every loaded file.
+#[derive(Default)]
+pub struct ViewSettings {
+    /// Timezone used for charts and displayed timestamps.
+    pub timezone: DisplayTimezone,
+    /// Heuristic used to estimate token counts.
+    pub tokenizer: TokenizerKind,
+    /// Model prices used for cost estimates.
+    pub prices: PriceTable,
+    /// Form of timestamps in JSON copied with "Copy Thread JSON", the only output it applies to;
+    /// `None` keeps the form each was loaded in. Exports for t3.chat always use milliseconds.
+    pub timestamp_form: Option<TimestampForm>,
 }
 
 pub struct MyApp {
@@ -33,19 +89,51 @@ pub struct MyApp {
     rt_handle: Handle,
 
     dropped_files: Vec<MyDroppedFile>,
+
+    // Settings applied to every file.
+    settings: ViewSettings,
+    show_price_table: bool,
+
+    // Comparison of all loaded files, shown in its own window.
+    comparison: ComparisonState,
+    show_comparison: bool,
+
+    // SQL over all loaded files, shown in its own window.
+    query: QueryState,
+    show_query: bool,
+
+    // Number of exports pasted so far, numbering their names.
+    pastes: usize,
 }
 
+// Files are only ever held in `MyApp::dropped_files`, so the variant size difference is fine.
+#[allow(clippy::large_enum_variant)]
 pub enum MyDroppedFile {
     T3Json {
+        /// Keys the file's window; see `MyDroppedFile::id`.
+        id: u64,
         file: egui::DroppedFile,
-        t3_json: T3Json,
+        /// Shared with background chart jobs.
+        t3_json: Arc<T3Json>,
         chart_state: ChartState,
-        search_query: String,
+        filter: ThreadFilter,
+        drill_down: Option<DrillDown>,
+        latency_state: LatencyState,
+        stats_state: StatsState,
+        cost_state: CostState,
+        reliability_state: ReliabilityState,
+        summary_state: SummaryState,
+        attachments_state: AttachmentsState,
     },
     Unknown {
+        id: u64,
         file: egui::DroppedFile,
     },
 }
+
+/// Source of `MyDroppedFile` ids.
+static NEXT_FILE_ID: AtomicU64 = AtomicU64::new(0);
+
 impl MyDroppedFile {
     pub async fn from_async(file: egui::DroppedFile) -> Self {
         info!(
@@ -55,25 +143,47 @@ impl MyDroppedFile {
         match T3Json::try_from_async(file.clone()).await {
             Ok(t3_json) => {
                 info!("Parsed T3Json successfully");
-                // Initialize ChartState when T3Json is successfully parsed
-                let chart_state = ChartState::new();
-                return MyDroppedFile::T3Json {
-                    file,
-                    t3_json,
-                    chart_state,
-                    search_query: String::new(),
-                };
+                return MyDroppedFile::loaded(file, t3_json);
             }
             Err(e) => {
                 warn!("Failed to parse T3Json {:?}: {:#?}", file.path, e);
             }
         }
-        MyDroppedFile::Unknown { file }
+        MyDroppedFile::Unknown {
+            id: NEXT_FILE_ID.fetch_add(1, Ordering::Relaxed),
+            file,
+        }
+    }
+    /// Wraps a parsed export with fresh view state.
+    pub fn loaded(file: egui::DroppedFile, t3_json: T3Json) -> Self {
+        MyDroppedFile::T3Json {
+            id: NEXT_FILE_ID.fetch_add(1, Ordering::Relaxed),
+            file,
+            t3_json: Arc::new(t3_json),
+            chart_state: ChartState::new(),
+            filter: ThreadFilter::default(),
+            drill_down: None,
+            latency_state: LatencyState::default(),
+            stats_state: StatsState::default(),
+            cost_state: CostState::default(),
+            reliability_state: ReliabilityState::default(),
+            summary_state: SummaryState::default(),
+            attachments_state: AttachmentsState::default(),
+        }
     }
+
     pub fn dropped_file(&self) -> &egui::DroppedFile {
         match self {
             MyDroppedFile::T3Json { file, .. } => file,
-            MyDroppedFile::Unknown { file } => file,
+            MyDroppedFile::Unknown { file, .. } => file,
+        }
+    }
+
+    /// Returns an id unique to this loaded file. Pasted exports and combined dumps have no
+    /// path, and the same file can be loaded twice, so windows are keyed on this instead.
+    pub fn id(&self) -> u64 {
+        match self {
+            MyDroppedFile::T3Json { id, .. } | MyDroppedFile::Unknown { id, .. } => *id,
         }
     }
 }
@@ -87,6 +197,13 @@ impl MyApp {
             rx,
             rt_handle: rt_handle.clone(),
             dropped_files: vec![],
+            settings: ViewSettings::default(),
+            show_price_table: false,
+            comparison: ComparisonState::default(),
+            show_comparison: false,
+            query: QueryState::default(),
+            show_query: false,
+            pastes: 0,
         };
 
         app.load_files(initial_paths);
@@ -94,6 +211,84 @@ impl MyApp {
         app
     }
 
+    /// Adds a loaded file. A dump holding only threads or only messages is combined with an
+    /// already loaded dump holding the other half, so both open as one export.
+    fn add_file(&mut self, file: MyDroppedFile) {
+        let MyDroppedFile::T3Json {
+            file: dropped,
+            t3_json,
+            ..
+        } = &file
+        else {
+            self.dropped_files.push(file);
+            return;
+        };
+        let Some(part) = DumpPart::of(t3_json) else {
+            self.dropped_files.push(file);
+            return;
+        };
+        let other = self.dropped_files.iter().position(|loaded| match loaded {
+            MyDroppedFile::T3Json { t3_json, .. } => DumpPart::of(t3_json) == Some(part.other()),
+            MyDroppedFile::Unknown { .. } => false,
+        });
+        let Some(MyDroppedFile::T3Json {
+            file: other_dropped,
+            t3_json: other_t3_json,
+            ..
+        }) = other.map(|index| self.dropped_files.remove(index))
+        else {
+            self.dropped_files.push(file);
+            return;
+        };
+
+        let (threads, messages) = match part {
+            DumpPart::Threads => ((dropped, t3_json), (&other_dropped, &other_t3_json)),
+            DumpPart::Messages => ((&other_dropped, &other_t3_json), (dropped, t3_json)),
+        };
+        info!(
+            "Combining threads from {} with messages from {}",
+            threads.0.name, messages.0.name
+        );
+        let combined = T3Json {
+            threads: threads.1.threads.clone(),
+            messages: messages.1.messages.clone(),
+        };
+        let dropped = egui::DroppedFile {
+            path: None,
+            name: format!("{} + {}", threads.0.name, messages.0.name),
+            mime: String::new(),
+            last_modified: None,
+            bytes: None,
+        };
+        self.dropped_files
+            .push(MyDroppedFile::loaded(dropped, combined));
+    }
+
+    /// Parses an export copied to the clipboard and opens it like a dropped file.
+    fn load_pasted(&mut self, text: String) {
+        info!("Loading pasted export of {} bytes", text.len());
+        // Numbered, since several pastes can happen within the same second
+        self.pastes += 1;
+        let file = egui::DroppedFile {
+            path: None,
+            name: format!(
+                "clipboard-{}-{}.json",
+                chrono::Local::now().format("%Y-%m-%dT%H-%M-%S"),
+                self.pastes
+            ),
+            mime: "application/json".to_string(),
+            last_modified: None,
+            bytes: Some(Arc::from(text.into_bytes())),
+        };
+        let tx = self.tx.clone();
+        self.rt_handle.spawn(async move {
+            tx.send(UiBoundMessage::ContentLoaded(
+                MyDroppedFile::from_async(file).await,
+            ))
+            .ok();
+        });
+    }
+
     fn load_files(&self, paths: Vec<PathBuf>) {
         if paths.is_empty() {
             return;
@@ -113,8 +308,10 @@ impl MyApp {
                     last_modified: None,
                     bytes: None,
                 };
-                tx.send(UiBoundMessage::ContentLoaded(MyDroppedFile::from_async(file).await))
-                    .ok();
+                tx.send(UiBoundMessage::ContentLoaded(
+                    MyDroppedFile::from_async(file).await,
+                ))
+                .ok();
             }
         });
     }
@@ -132,17 +329,101 @@ impl eframe::App for MyApp {
         // 2) Apply them
         for msg in new_messages {
             match msg {
-                UiBoundMessage::ContentLoaded(file) => self.dropped_files.push(file),
+                UiBoundMessage::ContentLoaded(file) => self.add_file(file),
+                UiBoundMessage::PricesLoaded(prices) => self.settings.prices = prices,
+                UiBoundMessage::ChartProcessed(job, data) => {
+                    // The file may have been closed while the chart was computed
+                    let chart_state = self.dropped_files.iter_mut().find_map(|file| match file {
+                        MyDroppedFile::T3Json { chart_state, .. }
+                            if chart_state.id() == job.state_id =>
+                        {
+                            Some(chart_state)
+                        }
+                        _ => None,
+                    });
+                    if let Some(chart_state) = chart_state {
+                        chart_state.receive(job, data);
+                    }
+                }
+                UiBoundMessage::QueryFinished(outcome) => self.query.receive(outcome),
             }
         }
 
+        egui::TopBottomPanel::top("settings_bar").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Timezone:");
+                self.settings.timezone.draw_picker(ui);
+                ui.separator();
+                ui.label("Tokens:");
+                egui::ComboBox::from_id_salt("tokenizer")
+                    .selected_text(self.settings.tokenizer.name())
+                    .show_ui(ui, |ui| {
+                        for kind in TokenizerKind::all() {
+                            ui.selectable_value(&mut self.settings.tokenizer, *kind, kind.name());
+                        }
+                    });
+                ui.label("Copy Thread JSON timestamps:").on_hover_text(
+                    "Only applies to \"Copy Thread JSON\". \
+                    Exports for t3.chat always use milliseconds, as t3.chat writes them.",
+                );
+                egui::ComboBox::from_id_salt("timestamp_form")
+                    .selected_text(
+                        self.settings
+                            .timestamp_form
+                            .map_or("As loaded", |form| form.name()),
+                    )
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.settings.timestamp_form, None, "As loaded");
+                        for form in TimestampForm::forcible() {
+                            ui.selectable_value(
+                                &mut self.settings.timestamp_form,
+                                Some(*form),
+                                form.name(),
+                            );
+                        }
+                    });
+                ui.separator();
+                ui.toggle_value(&mut self.show_price_table, "Price table");
+                ui.toggle_value(&mut self.show_comparison, "Compare files");
+                ui.toggle_value(&mut self.show_query, "SQL console");
+            });
+        });
+
+        self.ui_price_table(ctx);
+        self.ui_comparison(ctx);
+        self.ui_query(ctx);
+
         egui::CentralPanel::default().show(ctx, |ui| {
             if self.dropped_files.is_empty() {
+                // A paste into a focused text field, e.g. in the SQL console or the price table,
+                // is meant for that field
+                let pasted = ctx
+                    .input(|i| {
+                        i.events.iter().find_map(|event| match event {
+                            egui::Event::Paste(text) => Some(text.clone()),
+                            _ => None,
+                        })
+                    })
+                    .filter(|_| !ctx.wants_keyboard_input());
+                if let Some(text) = pasted {
+                    self.load_pasted(text);
+                }
                 ui.vertical(|ui| {
-                    ui.heading("Drag a .json export from t3.chat here to get started");
+                    ui.heading("Drag a .json export from t3.chat, or a .sqlite database saved by this viewer, here to get started");
+                    if ui
+                        .button("Paste export")
+                        .on_hover_text("Ctrl+V also works here")
+                        .clicked()
+                    {
+                        match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text())
+                        {
+                            Ok(text) => self.load_pasted(text),
+                            Err(e) => warn!("Failed to read the clipboard: {}", e),
+                        }
+                    }
                     ui.separator();
                     ui.label("Find the export option in t3.chat's settings.");
-                    ui.label("Alternatively, in Chrome/Edge devtools (F12), find the 'Application' tab -> 'Storage' -> 'IndexedDB' -> 't3.chat' -> 'chat_db'. You can inspect the 'messages' and 'threads' object stores here. You can also use the side note in the README.md to copy the full export JSON to your clipboard.");
+                    ui.label("Alternatively, in Chrome/Edge devtools (F12), find the 'Application' tab -> 'Storage' -> 'IndexedDB' -> 't3.chat' -> 'chat_db'. You can inspect the 'messages' and 'threads' object stores here. You can also use the side note in the README.md to copy the full export JSON to your clipboard, then paste it here.");
                 });
             }
         });
@@ -152,6 +433,139 @@ impl eframe::App for MyApp {
 }
 
 impl MyApp {
+    fn ui_price_table(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_price_table;
+        egui::Window::new("Price table")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.label("Dollars per million tokens, by model id.");
+                ui.horizontal(|ui| {
+                    if ui.button("Load…").clicked() {
+                        let tx = self.tx.clone();
+                        self.rt_handle.spawn(async move {
+                            let Some(handle) = rfd::AsyncFileDialog::new()
+                                .add_filter("JSON", &["json"])
+                                .pick_file()
+                                .await
+                            else {
+                                return;
+                            };
+                            match PriceTable::load(handle.path()) {
+                                Ok(prices) => {
+                                    tx.send(UiBoundMessage::PricesLoaded(prices)).ok();
+                                }
+                                Err(e) => warn!("{:#}", e),
+                            }
+                        });
+                    }
+                    if ui.button("Save…").clicked() {
+                        let prices = self.settings.prices.clone();
+                        self.rt_handle.spawn(async move {
+                            let Some(handle) = rfd::AsyncFileDialog::new()
+                                .add_filter("JSON", &["json"])
+                                .set_file_name("prices.json")
+                                .save_file()
+                                .await
+                            else {
+                                return;
+                            };
+                            if let Err(e) = prices.save(handle.path()) {
+                                warn!("{:#}", e);
+                            }
+                        });
+                    }
+                });
+                ui.separator();
+                let models: BTreeSet<&str> = self
+                    .dropped_files
+                    .iter()
+                    .filter_map(|file| match file {
+                        MyDroppedFile::T3Json { t3_json, .. } => Some(t3_json),
+                        MyDroppedFile::Unknown { .. } => None,
+                    })
+                    .flat_map(|t3_json| t3_json.messages.iter().map(|m| m.model.as_str()))
+                    .collect();
+                ScrollArea::vertical().show(ui, |ui| {
+                    draw_price_table(ui, &mut self.settings.prices, models.into_iter());
+                });
+            });
+        self.show_price_table = open;
+    }
+
+    fn ui_comparison(&mut self, ctx: &egui::Context) {
+        let mut files: Vec<ComparedFile> = vec![];
+        for dropped_file in &self.dropped_files {
+            let MyDroppedFile::T3Json {
+                file,
+                t3_json,
+                filter,
+                ..
+            } = dropped_file
+            else {
+                continue;
+            };
+            // Legend entries and the file picker are keyed by name, so keep names unique
+            let mut name = file.name.clone();
+            let mut copy = 2;
+            while files.iter().any(|compared| compared.name == name) {
+                name = format!("{} ({})", file.name, copy);
+                copy += 1;
+            }
+            files.push(ComparedFile {
+                name,
+                t3_json,
+                filter: filter.effective(),
+            });
+        }
+
+        let timezone = self.settings.timezone;
+        egui::Window::new("Compare files")
+            .open(&mut self.show_comparison)
+            .show(ctx, |ui| {
+                self.comparison.draw(ui, &files, timezone);
+            });
+    }
+
+    fn ui_query(&mut self, ctx: &egui::Context) {
+        let sources: Vec<Arc<T3Json>> = self
+            .dropped_files
+            .iter()
+            .filter_map(|file| match file {
+                MyDroppedFile::T3Json { t3_json, .. } => Some(Arc::clone(t3_json)),
+                MyDroppedFile::Unknown { .. } => None,
+            })
+            .collect();
+
+        let mut clicked = None;
+        egui::Window::new("SQL console")
+            .open(&mut self.show_query)
+            .show(ctx, |ui| {
+                clicked = self.query.draw(ui, &sources);
+            });
+        if let Some(job) = self.query.take_job() {
+            spawn_query_job(&self.rt_handle, &self.tx, job);
+        }
+
+        // Narrow every file containing the clicked thread down to it
+        let Some(thread_id) = clicked else {
+            return;
+        };
+        for file in &mut self.dropped_files {
+            if let MyDroppedFile::T3Json {
+                t3_json,
+                drill_down,
+                ..
+            } = file
+                && let Some(thread) = t3_json.threads.iter().find(|t| t.id == thread_id)
+            {
+                *drill_down = Some(DrillDown::Thread {
+                    id: thread.id.clone(),
+                    title: thread.title.clone(),
+                });
+            }
+        }
+    }
+
     fn ui_file_drag_and_drop(&mut self, ctx: &egui::Context) {
         use egui::Align2;
         use egui::Color32;
@@ -209,6 +623,9 @@ impl MyApp {
 
         // Show each dropped file in its own window:
         let mut indices_to_remove = vec![];
+        let settings = &self.settings;
+        let rt_handle = &self.rt_handle;
+        let ui_tx = &self.tx;
         for (index, file) in self.dropped_files.iter_mut().enumerate() {
             let mut open = true;
 
@@ -216,12 +633,10 @@ impl MyApp {
             let window_title = format!("Dropped File: {}", file.dropped_file().name);
 
             egui::Window::new(window_title)
-                .id(Id::new(
-                    file.dropped_file().path.clone().unwrap_or_default(),
-                )) // Use path or default for a stable ID
+                .id(Id::new(("dropped_file", file.id())))
                 .open(&mut open)
                 .show(ctx, |ui| {
-                    draw_dropped_file(file, ui);
+                    draw_dropped_file(file, ui, settings, rt_handle, ui_tx);
                 });
 
             // If the window is closed, mark the file for removal
@@ -237,15 +652,34 @@ impl MyApp {
     }
 }
 
-fn draw_dropped_file(file: &mut MyDroppedFile, ui: &mut egui::Ui) {
+fn draw_dropped_file(
+    file: &mut MyDroppedFile,
+    ui: &mut egui::Ui,
+    settings: &ViewSettings,
+    rt_handle: &Handle,
+    tx: &Sender<UiBoundMessage>,
+) {
+    let timezone = settings.timezone;
     // file needs to be mutable
     match file {
         MyDroppedFile::T3Json {
             file,
             t3_json,
             chart_state,
-            search_query,
+            filter,
+            drill_down,
+            latency_state,
+            stats_state,
+            cost_state,
+            reliability_state,
+            summary_state,
+            attachments_state,
+            ..
         } => {
+            let view = filter.apply(t3_json);
+            // Key for the caches below, unchanged by ticking threads in the thread list
+            let effective_filter = filter.effective();
+            chart_state.settings.timezone = timezone;
             ScrollArea::both().show(ui, |ui| {
                 // Get mutable access to chart_state
                 // Fix: Use as_deref() pattern for file_info
@@ -259,19 +693,16 @@ fn draw_dropped_file(file: &mut MyDroppedFile, ui: &mut egui::Ui) {
                 if let Some(bytes) = &file.bytes {
                     ui.label(format!("Size: {} bytes", bytes.len()));
                 }
+                if ui.button("Save as SQLite").clicked() {
+                    save_sqlite(rt_handle, t3_json, &file.name);
+                }
                 ui.separator();
                 ui.label("Parsed T3Json Content:");
 
-                // Display Thread count
-                ui.horizontal(|ui| {
-                    ui.label("Threads:");
-                    ui.monospace(format!("{}", t3_json.threads.len()));
-                });
-                // Display Message count
-                ui.horizontal(|ui| {
-                    ui.label("Messages:");
-                    ui.monospace(format!("{}", t3_json.messages.len()));
-                });
+                // Overview of the whole export
+                if let Some(clicked) = summary_state.draw(ui, t3_json, timezone) {
+                    *drill_down = Some(clicked);
+                }
 
                 ui.separator();
                 CollapsingHeader::new("Charts")
@@ -312,24 +743,128 @@ fn draw_dropped_file(file: &mut MyDroppedFile, ui: &mut egui::Ui) {
                                 // Reserve space for the plot
                                 let plot_height = 300.0; // Adjust height as needed
                                 ui.set_min_height(plot_height);
-                                chart_state.draw(ui, &t3_json.messages);
+                                if let Some(clicked) =
+                                    chart_state.draw(ui, &view.messages, &effective_filter)
+                                {
+                                    *drill_down = Some(clicked);
+                                }
+                                if let Some(export) = chart_state.take_export() {
+                                    save_export(rt_handle, export);
+                                }
+                                for job in chart_state.take_jobs() {
+                                    spawn_chart_job(rt_handle, tx, t3_json, job);
+                                }
                             },
                         );
                     });
 
+                CollapsingHeader::new("Statistics")
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        if let Some(clicked) =
+                            stats_state.draw(ui, &view, &effective_filter, settings.tokenizer)
+                        {
+                            *drill_down = Some(clicked);
+                        }
+                    });
+
+                CollapsingHeader::new("Cost Estimate")
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        if let Some(clicked) = cost_state.draw(
+                            ui,
+                            &view,
+                            &effective_filter,
+                            &settings.prices,
+                            settings.tokenizer,
+                            timezone,
+                        ) {
+                            *drill_down = Some(clicked);
+                        }
+                    });
+
+                CollapsingHeader::new("Reliability")
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        if let Some(clicked) =
+                            reliability_state.draw(ui, &view, &effective_filter, timezone)
+                        {
+                            *drill_down = Some(clicked);
+                        }
+                    });
+
+                CollapsingHeader::new("Slowest Responses")
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        if let Some(clicked) =
+                            latency_state.draw(ui, &view, &effective_filter, timezone)
+                        {
+                            *drill_down = Some(clicked);
+                        }
+                    });
+
+                CollapsingHeader::new("Attachments")
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        if let Some(clicked) =
+                            attachments_state.draw(ui, &view, &effective_filter, timezone)
+                        {
+                            *drill_down = Some(clicked);
+                        }
+                    });
+
                 ui.separator();
                 ui.heading("Threads");
+                draw_filter_controls(t3_json, ui, filter, &file_info);
+                let view = match drill_down {
+                    Some(bucket) => {
+                        let mut clear = false;
+                        ui.horizontal(|ui| {
+                            ui.label(format!("Showing {}", bucket.describe()));
+                            clear = ui.button("✕").clicked();
+                        });
+                        let view = view.drill_down(bucket, timezone);
+                        if clear {
+                            *drill_down = None;
+                        }
+                        view
+                    }
+                    None => view,
+                };
                 ui.horizontal(|ui| {
-                    ui.label("Search:");
-                    ui.text_edit_singleline(search_query);
-                    if !search_query.is_empty() && ui.button("✕").clicked() {
-                        search_query.clear();
+                    ui.label("Export shown threads:");
+                    for format in TableFormat::all() {
+                        if ui.small_button(format.name()).clicked() {
+                            match export_tables(&view, *format) {
+                                Ok(files) => save_to_folder(rt_handle, files),
+                                Err(e) => warn!("Failed to export tables: {:#}", e),
+                            }
+                        }
+                    }
+                    if ui
+                        .small_button("t3.chat JSON")
+                        .on_hover_text(
+                            "The shown threads with all their messages, for importing into t3.chat",
+                        )
+                        .clicked()
+                    {
+                        let subset = view.complete_threads(t3_json);
+                        match subset.to_import_json() {
+                            Ok(bytes) => save_export(
+                                rt_handle,
+                                ExportFile {
+                                    file_name: format!("{}-subset.json", source_stem(&file.name)),
+                                    bytes,
+                                },
+                            ),
+                            Err(e) => warn!("Failed to export threads: {:#}", e),
+                        }
                     }
                 });
-                draw_t3_json_threads(t3_json, ui, search_query);
+                draw_t3_json_threads(t3_json, &view, ui, filter, settings);
             });
         }
-        MyDroppedFile::Unknown { file } => {
+        MyDroppedFile::Unknown { file, .. } => {
             // Fix: Use as_deref() pattern for file_info
             let file_info = if let Some(path) = &file.path {
                 path.display().to_string()
@@ -352,40 +887,212 @@ fn draw_dropped_file(file: &mut MyDroppedFile, ui: &mut egui::Ui) {
     }
 }
 
-// Function to draw the threads part of the T3Json view
-fn draw_t3_json_threads(t3_json: &T3Json, ui: &mut egui::Ui, search_query: &str) {
-    use std::collections::HashMap;
-
-    let query = search_query.to_lowercase();
-
-    // When a search is active, collect matching threads and show a count
-    let matching_threads: Vec<&crate::t3_json::T3Thread> = if query.is_empty() {
-        t3_json.threads.iter().collect()
-    } else {
-        // Build a thread_id -> messages index only when a search is active
-        let messages_by_thread: HashMap<&str, Vec<&crate::t3_json::T3Message>> =
-            t3_json.messages.iter().fold(HashMap::new(), |mut map, m| {
-                map.entry(m.thread_id.as_str()).or_default().push(m);
-                map
-            });
-        t3_json
-            .threads
-            .iter()
-            .filter(|thread| {
-                thread.title.to_lowercase().contains(&query)
-                    || messages_by_thread
-                        .get(thread.id.as_str())
-                        .map_or(false, |msgs| {
-                            msgs.iter().any(|m| m.content.to_lowercase().contains(&query))
-                        })
-            })
-            .collect()
-    };
+/// Computes chart data on the runtime's blocking pool and sends it back to the UI.
+fn spawn_chart_job(
+    rt_handle: &Handle,
+    tx: &Sender<UiBoundMessage>,
+    t3_json: &Arc<T3Json>,
+    job: ChartJob,
+) {
+    let tx = tx.clone();
+    let t3_json = Arc::clone(t3_json);
+    rt_handle.spawn_blocking(move || {
+        let data = job.run(&t3_json);
+        tx.send(UiBoundMessage::ChartProcessed(job, data)).ok();
+    });
+}
+
+/// Builds the query database and runs the query on the runtime's blocking pool.
+fn spawn_query_job(rt_handle: &Handle, tx: &Sender<UiBoundMessage>, job: QueryJob) {
+    let tx = tx.clone();
+    rt_handle.spawn_blocking(move || {
+        tx.send(UiBoundMessage::QueryFinished(job.run())).ok();
+    });
+}
+
+/// Returns the name of a loaded file without its extension, for naming files derived from it.
+fn source_stem(file_name: &str) -> String {
+    Path::new(file_name)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "export".to_string())
+}
+
+/// Asks where to save the export as a SQLite database and writes it there.
+fn save_sqlite(rt_handle: &Handle, t3_json: &Arc<T3Json>, file_name: &str) {
+    let t3_json = Arc::clone(t3_json);
+    let stem = source_stem(file_name);
+    rt_handle.spawn(async move {
+        let Some(handle) = rfd::AsyncFileDialog::new()
+            .add_filter("SQLite", &["sqlite", "db"])
+            .set_file_name(format!("{}.sqlite", stem))
+            .save_file()
+            .await
+        else {
+            return;
+        };
+        let path = handle.path().to_path_buf();
+        let written = tokio::task::spawn_blocking({
+            let path = path.clone();
+            move || sqlite::write(&t3_json, &path)
+        })
+        .await
+        .map_err(eyre::Report::from)
+        .and_then(|written| written);
+        match written {
+            Ok(()) => info!("Saved {}", path.display()),
+            Err(e) => warn!("Failed to write {}: {:#}", path.display(), e),
+        }
+    });
+}
+
+/// Asks for a folder and writes the exported files into it.
+fn save_to_folder(rt_handle: &Handle, files: Vec<ExportFile>) {
+    rt_handle.spawn(async move {
+        let Some(handle) = rfd::AsyncFileDialog::new().pick_folder().await else {
+            return;
+        };
+        for file in files {
+            let path = handle.path().join(&file.file_name);
+            match std::fs::write(&path, &file.bytes) {
+                Ok(()) => info!("Exported {}", path.display()),
+                Err(e) => warn!("Failed to write {}: {:#}", path.display(), e),
+            }
+        }
+    });
+}
+
+/// Asks where to save an exported file and writes it there.
+fn save_export(rt_handle: &Handle, export: ExportFile) {
+    rt_handle.spawn(async move {
+        let Some(handle) = rfd::AsyncFileDialog::new()
+            .set_file_name(&export.file_name)
+            .save_file()
+            .await
+        else {
+            return;
+        };
+        match std::fs::write(handle.path(), &export.bytes) {
+            Ok(()) => info!("Exported {}", handle.path().display()),
+            Err(e) => warn!("Failed to write {}: {:#}", handle.path().display(), e),
+        }
+    });
+}
+
+// Function to draw the search box and facets that make up the ThreadFilter
+fn draw_filter_controls(
+    t3_json: &T3Json,
+    ui: &mut egui::Ui,
+    filter: &mut ThreadFilter,
+    file_info: &str,
+) {
+    ui.horizontal(|ui| {
+        ui.label("Search:");
+        ui.text_edit_singleline(&mut filter.search_query);
+        if !filter.search_query.is_empty() && ui.button("✕").clicked() {
+            filter.search_query.clear();
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Models:");
+        let models: BTreeSet<&str> = t3_json.messages.iter().map(|m| m.model.as_str()).collect();
+        let selected_text = if filter.models.is_empty() {
+            "All models".to_string()
+        } else {
+            format!("{} selected", filter.models.len())
+        };
+        egui::ComboBox::new(format!("model_facet_{}", file_info), selected_text).show_ui(
+            ui,
+            |ui| {
+                for model in models {
+                    let mut checked = filter.models.contains(model);
+                    if ui.checkbox(&mut checked, model).changed() {
+                        if checked {
+                            filter.models.insert(model.to_string());
+                        } else {
+                            filter.models.remove(model);
+                        }
+                    }
+                }
+            },
+        );
+        if !filter.models.is_empty() && ui.button("✕").clicked() {
+            filter.models.clear();
+        }
 
-    if !query.is_empty() {
+        ui.separator();
+        ui.label("Reasoning:");
+        let efforts: BTreeSet<Option<&ReasoningEffort>> =
+            t3_json.messages.iter().map(reasoning_effort).collect();
+        let selected_text = if filter.reasoning_efforts.is_empty() {
+            "Any effort".to_string()
+        } else {
+            format!("{} selected", filter.reasoning_efforts.len())
+        };
+        egui::ComboBox::new(format!("reasoning_facet_{}", file_info), selected_text).show_ui(
+            ui,
+            |ui| {
+                for effort in efforts {
+                    let effort = effort.cloned();
+                    let mut checked = filter.reasoning_efforts.contains(&effort);
+                    if ui
+                        .checkbox(&mut checked, ReasoningEffort::label(effort.as_ref()))
+                        .changed()
+                    {
+                        if checked {
+                            filter.reasoning_efforts.insert(effort);
+                        } else {
+                            filter.reasoning_efforts.remove(&effort);
+                        }
+                    }
+                }
+            },
+        );
+        if !filter.reasoning_efforts.is_empty() && ui.button("✕").clicked() {
+            filter.reasoning_efforts.clear();
+        }
+
+        ui.label("Web search:");
+        let search_name = |web_search: Option<bool>| match web_search {
+            None => "Any",
+            Some(true) => "On",
+            Some(false) => "Off",
+        };
+        egui::ComboBox::new(
+            format!("search_facet_{}", file_info),
+            search_name(filter.web_search),
+        )
+        .show_ui(ui, |ui| {
+            for web_search in [None, Some(true), Some(false)] {
+                ui.selectable_value(&mut filter.web_search, web_search, search_name(web_search));
+            }
+        });
+
+        ui.separator();
+        ui.checkbox(
+            &mut filter.selected_only,
+            format!("Only selected ({})", filter.selected_threads.len()),
+        );
+        if !filter.selected_threads.is_empty() && ui.button("Clear selection").clicked() {
+            filter.selected_threads.clear();
+            filter.selected_only = false;
+        }
+    });
+}
+
+// Function to draw the threads part of the T3Json view
+fn draw_t3_json_threads(
+    t3_json: &T3Json,
+    view: &FilteredView,
+    ui: &mut egui::Ui,
+    filter: &mut ThreadFilter,
+    settings: &ViewSettings,
+) {
+    let timezone = settings.timezone;
+    if !filter.is_empty() || view.threads.len() != t3_json.threads.len() {
         ui.label(format!(
             "{} / {} threads match",
-            matching_threads.len(),
+            view.threads.len(),
             t3_json.threads.len()
         ));
     }
@@ -394,7 +1101,7 @@ fn draw_t3_json_threads(t3_json: &T3Json, ui: &mut egui::Ui, search_query: &str)
     // Use ScrollArea here to contain the threads list specifically
     ScrollArea::vertical().show(ui, |ui| {
         // vertical scroll for the threads list
-        for thread in &matching_threads {
+        for thread in &view.threads {
             // Use a simpler title if the actual title is too long for the header
             let display_title = if thread.title.len() > 80 {
                 // Arbitrary length limit
@@ -403,84 +1110,113 @@ fn draw_t3_json_threads(t3_json: &T3Json, ui: &mut egui::Ui, search_query: &str)
                 thread.title.clone()
             };
 
-            egui::CollapsingHeader::new(format!("Thread: {}", display_title))
-                .default_open(false)
-                .id_salt(thread.id.clone()) // Use thread ID for stable header state
-                .show(ui, |ui| {
-                    // Use an inner ScrollArea for messages within a thread if they are numerous
-                    ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
-                        // Example max height
-                        ui.horizontal(|ui| {
-                            if ui.button("Open Thread").clicked() {
-                                ui.ctx().open_url(egui::OpenUrl::new_tab(thread_url(&thread.id)));
-                            }
+            egui::collapsing_header::CollapsingState::load_with_default_open(
+                ui.ctx(),
+                egui::Id::new(&thread.id), // Use thread ID for stable header state
+                false,
+            )
+            .show_header(ui, |ui| {
+                let mut selected = filter.selected_threads.contains(&thread.id);
+                if ui.checkbox(&mut selected, "").changed() {
+                    if selected {
+                        filter.selected_threads.insert(thread.id.clone());
+                    } else {
+                        filter.selected_threads.remove(&thread.id);
+                    }
+                }
+                ui.label(format!("Thread: {}", display_title));
+            })
+            .body(|ui| {
+                // Use an inner ScrollArea for messages within a thread if they are numerous
+                ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                    // Example max height
+                    ui.horizontal(|ui| {
+                        if ui.button("Open Thread").clicked() {
+                            ui.ctx()
+                                .open_url(egui::OpenUrl::new_tab(thread_url(&thread.id)));
+                        }
 
-                            // Add a "Copy" button
-                            if ui.button("Copy Thread JSON").clicked() {
-                                // Collect the thread and its associated messages
-                                let thread_data = serde_json::json!({
+                        // Add a "Copy" button
+                        if ui.button("Copy Thread JSON").clicked() {
+                            // Collect the thread and its associated messages
+                            let thread_data = serialize_as(settings.timestamp_form, || {
+                                serde_json::json!({
                                     "thread": thread,
                                     "messages": t3_json.messages.iter()
                                         .filter(|m| m.thread_id == thread.id)
                                         .collect::<Vec<_>>(),
-                                });
-
-                                // Copy the JSON to the clipboard
-                                if let Ok(json_string) = serde_json::to_string_pretty(&thread_data)
-                                {
-                                    ui.output_mut(|o| {
-                                        o.commands.push(egui::OutputCommand::CopyText(json_string))
-                                    });
-                                } else {
-                                    ui.label("Failed to serialize thread data.");
-                                }
-                            }
+                                })
+                            });
 
-                            // Display the full thread title if truncated in the header
-                            if thread.title.len() > 80 {
-                                ui.label(format!("Full Title: {}", thread.title));
+                            // Copy the JSON to the clipboard
+                            if let Ok(json_string) = serde_json::to_string_pretty(&thread_data) {
+                                ui.output_mut(|o| {
+                                    o.commands.push(egui::OutputCommand::CopyText(json_string))
+                                });
+                            } else {
+                                ui.label("Failed to serialize thread data.");
                             }
-                        });
+                        }
 
-                        ui.label(format!("Thread ID: {}", thread.id));
-                        ui.label(format!("Created At: {}", thread.created_at));
-                        if let Some(updated_at) = thread.updated_at {
-                            ui.label(format!("Updated At: {}", updated_at));
+                        // Display the full thread title if truncated in the header
+                        if thread.title.len() > 80 {
+                            ui.label(format!("Full Title: {}", thread.title));
                         }
-                        ui.label(format!("Last Message At: {}", thread.last_message_at));
-                        ui.label(format!("Status: {:?}", thread.status));
-                        ui.separator();
+                    });
 
-                        // Show messages within the thread
-                        for message in t3_json.messages.iter().filter(|m| m.thread_id == thread.id)
-                        {
-                            ui.horizontal(|ui| {
-                                ui.label(format!("Role: {:?}", message.role));
-                                ui.label(format!(
-                                    "Message: {}",
-                                    // Truncate long messages for display
-                                    if message.content.len() > 500 {
-                                        // Increase truncation length slightly
-                                        format!("{}...", &message.content[..500])
-                                    } else {
-                                        message.content.clone()
-                                    }
-                                ));
-                            });
-                            // Add a button to copy the individual message content
-                            ui.horizontal(|ui| {
-                                if ui.button("Copy Message Content").clicked() {
-                                    ui.output_mut(|o| {
-                                        o.commands.push(egui::OutputCommand::CopyText(
-                                            message.content.clone(),
-                                        ))
-                                    });
+                    ui.label(format!("Thread ID: {}", thread.id));
+                    ui.label(format!(
+                        "Created At: {}",
+                        timezone.format(*thread.created_at)
+                    ));
+                    if let Some(updated_at) = thread.updated_at {
+                        ui.label(format!("Updated At: {}", timezone.format(*updated_at)));
+                    }
+                    ui.label(format!(
+                        "Last Message At: {}",
+                        timezone.format(*thread.last_message_at)
+                    ));
+                    ui.label(format!("Status: {:?}", thread.status));
+                    ui.separator();
+
+                    // Show messages within the thread
+                    for message in t3_json.messages.iter().filter(|m| m.thread_id == thread.id) {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("Role: {:?}", message.role));
+                            ui.label(format!(
+                                "Message: {}",
+                                // Truncate long messages for display
+                                if message.content.len() > 500 {
+                                    // Increase truncation length slightly
+                                    format!("{}...", &message.content[..500])
+                                } else {
+                                    message.content.clone()
                                 }
-                            });
-                            ui.separator(); // Separator between messages
+                            ));
+                        });
+                        if let Some(params) = &message.model_params {
+                            let labels = params.labels();
+                            if !labels.is_empty() {
+                                ui.weak(labels.join(" · "));
+                            }
                         }
-                    }); // End of inner ScrollArea for messages
-                });
+                        for attachment in message.attachments.iter().flatten() {
+                            draw_attachment(ui, attachment);
+                        }
+                        // Add a button to copy the individual message content
+                        ui.horizontal(|ui| {
+                            if ui.button("Copy Message Content").clicked() {
+                                ui.output_mut(|o| {
+                                    o.commands.push(egui::OutputCommand::CopyText(
+                                        message.content.clone(),
+                                    ))
+                                });
+                            }
+                        });
+                        ui.separator(); // Separator between messages
+                    }
+                }); // End of inner ScrollArea for messages
+            });
         }
     }); // End of outer ScrollArea for threads list
 }
@@ -0,0 +1,371 @@
+// src/attachment.rs
+
+use chrono::DateTime;
+use chrono::Utc;
+use eframe::egui;
+use egui_extras::Column;
+use egui_extras::TableBuilder;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+use serde_json::Map;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::filter::DrillDown;
+use crate::filter::FilteredView;
+use crate::filter::ThreadFilter;
+use crate::timezone::DisplayTimezone;
+
+/// Keys each field has been seen under, most common first.
+const NAME_KEYS: [&str; 4] = ["fileName", "file_name", "filename", "name"];
+const MIME_TYPE_KEYS: [&str; 5] = [
+    "mimeType",
+    "mime_type",
+    "contentType",
+    "content_type",
+    "type",
+];
+const URL_KEYS: [&str; 3] = ["url", "fileUrl", "attachmentUrl"];
+const KEY_KEYS: [&str; 3] = ["key", "fileKey", "storageKey"];
+const SIZE_KEYS: [&str; 3] = ["size", "fileSize", "file_size"];
+
+/// A file attached to a message. Every field is optional since exports differ in what
+/// they record.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct T3File {
+    pub file_name: Option<String>,
+    pub mime_type: Option<String>,
+    pub url: Option<String>,
+    /// Storage key, for attachments exported without a URL.
+    pub key: Option<String>,
+    /// Size in bytes.
+    pub size: Option<u64>,
+}
+
+impl T3File {
+    /// Returns the URL if it is safe to open in a browser, i.e. an `http` or `https` URL.
+    pub fn web_url(&self) -> Option<&str> {
+        let url = self.url.as_deref()?;
+        let (scheme, _) = url.split_once(':')?;
+        (scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https")).then_some(url)
+    }
+}
+
+/// An attachment as read from an export. The original JSON is kept so saving an export
+/// writes attachments back exactly as they were loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum T3Attachment {
+    File {
+        file: T3File,
+        raw: Value,
+    },
+    /// Anything without a name, URL or key.
+    Unknown(Value),
+}
+
+impl T3Attachment {
+    pub fn from_value(value: Value) -> Self {
+        let file = match &value {
+            Value::String(url) => T3File {
+                url: Some(url.clone()),
+                ..T3File::default()
+            },
+            Value::Object(object) => T3File {
+                file_name: string_field(object, &NAME_KEYS),
+                // `type` is sometimes a kind like "image" rather than a mime type
+                mime_type: MIME_TYPE_KEYS
+                    .iter()
+                    .filter_map(|key| object.get(*key)?.as_str())
+                    .find(|mime_type| mime_type.contains('/'))
+                    .map(str::to_string),
+                url: string_field(object, &URL_KEYS),
+                key: string_field(object, &KEY_KEYS),
+                size: SIZE_KEYS.iter().find_map(|key| object.get(*key)?.as_u64()),
+            },
+            _ => T3File::default(),
+        };
+        if file.file_name.is_none() && file.url.is_none() && file.key.is_none() {
+            T3Attachment::Unknown(value)
+        } else {
+            T3Attachment::File { file, raw: value }
+        }
+    }
+
+    pub fn file(&self) -> Option<&T3File> {
+        match self {
+            T3Attachment::File { file, .. } => Some(file),
+            T3Attachment::Unknown(_) => None,
+        }
+    }
+
+    pub fn raw(&self) -> &Value {
+        match self {
+            T3Attachment::File { raw, .. } => raw,
+            T3Attachment::Unknown(raw) => raw,
+        }
+    }
+
+    /// Returns the file name, falling back to the last segment of the URL or key.
+    pub fn display_name(&self) -> String {
+        let Some(file) = self.file() else {
+            return "Unknown attachment".to_string();
+        };
+        file.file_name
+            .clone()
+            .or_else(|| {
+                file.url
+                    .as_deref()
+                    .or(file.key.as_deref())
+                    .and_then(|path| path.split(['/', '?']).rfind(|s| !s.is_empty()))
+                    .map(str::to_string)
+            })
+            .unwrap_or_else(|| "Unnamed file".to_string())
+    }
+
+    /// Describes the type and size, e.g. "image/png, 12.3 KB".
+    pub fn details(&self) -> String {
+        let Some(file) = self.file() else {
+            return String::new();
+        };
+        [file.mime_type.clone(), file.size.map(format_size)]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+fn string_field(object: &Map<String, Value>, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .find_map(|key| object.get(*key)?.as_str())
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+impl Serialize for T3Attachment {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.raw().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for T3Attachment {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Value::deserialize(deserializer).map(T3Attachment::from_value)
+    }
+}
+
+/// Formats a byte count with binary units.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Draws an attachment as a single line: its name, followed by its type and size.
+pub fn draw_attachment(ui: &mut egui::Ui, attachment: &T3Attachment) {
+    ui.horizontal(|ui| {
+        draw_attachment_name(ui, attachment);
+        let details = attachment.details();
+        if !details.is_empty() {
+            ui.weak(details);
+        }
+    });
+}
+
+/// Draws the name of an attachment: a link when it has a web URL, with the raw JSON on hover.
+/// Other URLs, such as `file:` or `javascript:`, come from the export and aren't opened.
+fn draw_attachment_name(ui: &mut egui::Ui, attachment: &T3Attachment) {
+    ui.label("📎");
+    let name = attachment.display_name();
+    let response = match attachment.file().and_then(T3File::web_url) {
+        Some(url) => ui.hyperlink_to(name, url),
+        None => ui.label(name),
+    };
+    response.on_hover_text(serde_json::to_string_pretty(attachment.raw()).unwrap_or_default());
+}
+
+/// A row of the attachments browser. Owned so it can be cached between frames.
+struct AttachmentRow {
+    attachment: T3Attachment,
+    thread_id: String,
+    thread_title: String,
+    sent_at: DateTime<Utc>,
+}
+
+/// State for the list of every attachment in the shown threads.
+#[derive(Default)]
+pub struct AttachmentsState {
+    /// Rows computed for a filter, newest first.
+    rows: Option<(ThreadFilter, Vec<AttachmentRow>)>,
+}
+
+impl AttachmentsState {
+    /// Draws a table of attachments. Clicking a thread title returns a drill-down to that
+    /// thread.
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        view: &FilteredView,
+        filter: &ThreadFilter,
+        timezone: DisplayTimezone,
+    ) -> Option<DrillDown> {
+        if self
+            .rows
+            .as_ref()
+            .is_none_or(|(cached, _)| cached != filter)
+        {
+            let titles: HashMap<&str, &str> = view
+                .threads
+                .iter()
+                .map(|t| (t.id.as_str(), t.title.as_str()))
+                .collect();
+            let mut rows: Vec<AttachmentRow> = view
+                .messages
+                .iter()
+                .flat_map(|message| {
+                    message
+                        .attachments
+                        .iter()
+                        .flatten()
+                        .map(|attachment| AttachmentRow {
+                            attachment: attachment.clone(),
+                            thread_id: message.thread_id.clone(),
+                            thread_title: titles
+                                .get(message.thread_id.as_str())
+                                .copied()
+                                .unwrap_or_default()
+                                .to_string(),
+                            sent_at: *message.created_at,
+                        })
+                })
+                .collect();
+            rows.sort_by_key(|row| std::cmp::Reverse(row.sent_at));
+            self.rows = Some((filter.clone(), rows));
+        }
+        let Some((_, rows)) = &self.rows else {
+            return None;
+        };
+
+        let unknown = rows
+            .iter()
+            .filter(|row| row.attachment.file().is_none())
+            .count();
+        ui.label(if unknown == 0 {
+            format!("{} attachments", rows.len())
+        } else {
+            format!(
+                "{} attachments, {} in an unknown shape",
+                rows.len(),
+                unknown
+            )
+        });
+        if rows.is_empty() {
+            return None;
+        }
+
+        let mut drill_down = None;
+        TableBuilder::new(ui)
+            .id_salt("attachments")
+            .striped(true)
+            .max_scroll_height(300.0)
+            .column(Column::auto().at_most(300.0).clip(true))
+            .column(Column::auto())
+            .column(Column::remainder().at_least(150.0).clip(true))
+            .column(Column::auto())
+            .header(20.0, |mut header| {
+                for title in ["File", "Type", "Thread", "Sent At"] {
+                    header.col(|ui| {
+                        ui.strong(title);
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(18.0, rows.len(), |mut row| {
+                    let attachment = &rows[row.index()];
+                    row.col(|ui| {
+                        // Type and size have their own column
+                        ui.horizontal(|ui| draw_attachment_name(ui, &attachment.attachment));
+                    });
+                    row.col(|ui| {
+                        ui.label(attachment.attachment.details());
+                    });
+                    row.col(|ui| {
+                        if ui.link(&attachment.thread_title).clicked() {
+                            drill_down = Some(DrillDown::Thread {
+                                id: attachment.thread_id.clone(),
+                                title: attachment.thread_title.clone(),
+                            });
+                        }
+                    });
+                    row.col(|ui| {
+                        ui.label(timezone.format(attachment.sent_at));
+                    });
+                });
+            });
+        drill_down
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::T3Attachment;
+    use super::T3File;
+    use super::format_size;
+    use serde_json::json;
+
+    #[test]
+    fn reads_known_shapes_and_keeps_the_rest() {
+        let raw = json!({
+            "fileName": "diagram.png",
+            "mimeType": "image/png",
+            "url": "https://example.com/f/abc",
+            "size": 12_800,
+            "extra": true,
+        });
+        let attachment: T3Attachment = serde_json::from_value(raw.clone()).unwrap();
+        assert_eq!(
+            attachment.file(),
+            Some(&T3File {
+                file_name: Some("diagram.png".to_string()),
+                mime_type: Some("image/png".to_string()),
+                url: Some("https://example.com/f/abc".to_string()),
+                key: None,
+                size: Some(12_800),
+            })
+        );
+        assert_eq!(attachment.details(), "image/png, 12.5 KB");
+        assert_eq!(
+            attachment.file().and_then(T3File::web_url),
+            Some("https://example.com/f/abc")
+        );
+        // Only web URLs are opened
+        for url in [
+            "javascript:alert(1)",
+            "file:///etc/passwd",
+            "ftp://example.com/f",
+        ] {
+            let attachment = T3Attachment::from_value(json!({ "url": url }));
+            assert_eq!(attachment.file().and_then(T3File::web_url), None, "{}", url);
+        }
+        // Written back unchanged, including keys that aren't modelled
+        assert_eq!(serde_json::to_value(&attachment).unwrap(), raw);
+
+        let keyed = T3Attachment::from_value(json!({ "key": "uploads/notes.pdf", "type": "file" }));
+        assert_eq!(keyed.display_name(), "notes.pdf");
+        assert_eq!(keyed.file().and_then(|file| file.mime_type.clone()), None);
+
+        let unknown = T3Attachment::from_value(json!({ "kind": "image" }));
+        assert_eq!(unknown, T3Attachment::Unknown(json!({ "kind": "image" })));
+        assert_eq!(format_size(512), "512 B");
+    }
+}
@@ -0,0 +1,607 @@
+// src/chart_export.rs
+
+use chrono::DateTime;
+use chrono::Datelike;
+use chrono::Duration;
+use chrono::NaiveDate;
+use chrono::Utc;
+use eframe::egui;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::f64::consts::FRAC_PI_2;
+use std::f64::consts::TAU;
+use std::fmt::Write as _;
+
+use crate::charts::ProcessedChartData;
+use crate::charts::heatmap_color;
+use crate::charts::series_color;
+
+const SVG_WIDTH: f64 = 800.0;
+const SVG_HEIGHT: f64 = 400.0;
+const MARGIN_LEFT: f64 = 60.0;
+const MARGIN_RIGHT: f64 = 180.0;
+const MARGIN_TOP: f64 = 40.0;
+const MARGIN_BOTTOM: f64 = 40.0;
+/// Maximum number of x axis labels drawn in an SVG, so long time series stay readable.
+const MAX_X_LABELS: usize = 12;
+
+/// The formats a chart can be exported in.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Png,
+    Svg,
+}
+
+impl ExportFormat {
+    /// Returns the file extension, which doubles as the button label.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Png => "png",
+            ExportFormat::Svg => "svg",
+        }
+    }
+
+    /// Returns all available formats.
+    pub fn all() -> &'static [ExportFormat] {
+        &[
+            ExportFormat::Csv,
+            ExportFormat::Json,
+            ExportFormat::Png,
+            ExportFormat::Svg,
+        ]
+    }
+}
+
+/// A file produced by an export, waiting for the user to pick where to save it.
+pub struct ExportFile {
+    pub file_name: String,
+    pub bytes: Vec<u8>,
+}
+
+/// One value of a chart: the series it belongs to, its x label and its value.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct ChartRow {
+    pub series: String,
+    pub label: String,
+    pub value: f64,
+}
+
+impl ProcessedChartData {
+    /// Flattens the chart into one row per value.
+    /// Time series are labelled with their bucket start in `time_format`, box plots with one row
+    /// per quartile.
+    pub fn rows(&self, time_format: &str) -> Vec<ChartRow> {
+        let row = |series: &str, label: String, value: f64| ChartRow {
+            series: series.to_string(),
+            label,
+            value,
+        };
+        let label_at = |labels: &[String], argument: f64| {
+            labels
+                .get(argument.round() as usize)
+                .cloned()
+                .unwrap_or_default()
+        };
+        match self {
+            ProcessedChartData::Bars { points, labels } => points
+                .iter()
+                .map(|bar| row("Messages", label_at(labels, bar.argument), bar.value))
+                .collect(),
+            ProcessedChartData::StackedBars { series, labels } => series
+                .iter()
+                .flat_map(|s| {
+                    s.values
+                        .iter()
+                        .map(|bar| row(&s.name, label_at(labels, bar.argument), bar.value))
+                })
+                .collect(),
+            ProcessedChartData::Lines { series } => series
+                .iter()
+                .flat_map(|s| {
+                    s.values
+                        .iter()
+                        .map(|point| row(&s.name, time_label(point.x, time_format), point.y))
+                })
+                .collect(),
+            ProcessedChartData::Pie { slices } => slices
+                .iter()
+                .map(|(name, value)| row("Messages", name.clone(), *value))
+                .collect(),
+            ProcessedChartData::Calendar { counts } => counts
+                .iter()
+                .map(|(day, count)| row("Messages", day.to_string(), *count as f64))
+                .collect(),
+            ProcessedChartData::Boxes { points, .. } => points
+                .iter()
+                .flat_map(|elem| {
+                    let spread = &elem.spread;
+                    [
+                        ("lower_whisker", spread.lower_whisker),
+                        ("q1", spread.quartile1),
+                        ("median", spread.median),
+                        ("q3", spread.quartile3),
+                        ("upper_whisker", spread.upper_whisker),
+                    ]
+                    .map(|(label, value)| row(&elem.name, label.to_string(), value))
+                })
+                .collect(),
+            ProcessedChartData::None => vec![],
+        }
+    }
+}
+
+/// Formats an x value holding seconds since the epoch.
+fn time_label(seconds: f64, time_format: &str) -> String {
+    DateTime::<Utc>::from_timestamp(seconds.round() as i64, 0)
+        .map(|date_time| date_time.format(time_format).to_string())
+        .unwrap_or_default()
+}
+
+/// Writes the rows as CSV with a `series,label,value` header.
+pub fn to_csv(rows: &[ChartRow]) -> eyre::Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    for row in rows {
+        writer.serialize(row)?;
+    }
+    Ok(writer.into_inner()?)
+}
+
+/// Writes the rows as a JSON document with the chart's name.
+pub fn to_json(chart: &str, rows: &[ChartRow]) -> eyre::Result<Vec<u8>> {
+    let document = serde_json::json!({ "chart": chart, "rows": rows });
+    Ok(serde_json::to_vec_pretty(&document)?)
+}
+
+/// Encodes a screenshot region as a PNG.
+pub fn to_png(image: &egui::ColorImage) -> eyre::Result<Vec<u8>> {
+    let mut bytes = vec![];
+    let [width, height] = image.size;
+    let mut encoder = png::Encoder::new(&mut bytes, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(image.as_raw())?;
+    writer.finish()?;
+    Ok(bytes)
+}
+
+/// Turns a chart name into a file name stem, e.g. "Models over Time" -> "models-over-time".
+pub fn file_stem(chart: &str) -> String {
+    chart
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn hex(color: egui::Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Formats an axis value, without decimals for whole numbers.
+pub fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+/// Renders the chart as a standalone SVG document.
+///
+/// This is a static rendering of the processed data rather than a capture of the plot, so it
+/// shows the whole chart regardless of how the plot is zoomed. `year` picks the calendar year.
+pub fn to_svg(
+    chart: &str,
+    data: &ProcessedChartData,
+    time_format: &str,
+    year: Option<i32>,
+    format_y: fn(f64) -> String,
+) -> String {
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="11">"#,
+        w = SVG_WIDTH,
+        h = SVG_HEIGHT
+    )
+    .ok();
+    writeln!(
+        svg,
+        r#"<rect width="100%" height="100%" fill="white"/><text x="{}" y="24" font-size="16">{}</text>"#,
+        MARGIN_LEFT,
+        escape(chart)
+    )
+    .ok();
+
+    match data {
+        ProcessedChartData::Bars { points, labels } => {
+            let values: Vec<f64> = points.iter().map(|bar| bar.value).collect();
+            let frame = Frame::new(labels.len(), max_of(values.iter().copied()));
+            frame.axes(&mut svg, labels, format_y);
+            for bar in points {
+                frame.bar(
+                    &mut svg,
+                    bar.argument,
+                    0.0,
+                    bar.value,
+                    egui::Color32::LIGHT_BLUE,
+                );
+            }
+        }
+        ProcessedChartData::StackedBars { series, labels } => {
+            let mut totals = vec![0.0; labels.len()];
+            for bar in series.iter().flat_map(|s| &s.values) {
+                if let Some(total) = totals.get_mut(bar.argument.round() as usize) {
+                    *total += bar.value;
+                }
+            }
+            let frame = Frame::new(labels.len(), max_of(totals.iter().copied()));
+            frame.axes(&mut svg, labels, format_y);
+            let mut base = vec![0.0; labels.len()];
+            for (index, s) in series.iter().enumerate() {
+                for bar in &s.values {
+                    let Some(bottom) = base.get_mut(bar.argument.round() as usize) else {
+                        continue;
+                    };
+                    frame.bar(
+                        &mut svg,
+                        bar.argument,
+                        *bottom,
+                        bar.value,
+                        series_color(index),
+                    );
+                    *bottom += bar.value;
+                }
+            }
+            legend(&mut svg, series.iter().map(|s| s.name.as_str()));
+        }
+        ProcessedChartData::Lines { series } => {
+            let xs = series.iter().flat_map(|s| s.values.iter().map(|p| p.x));
+            let min_x = xs.clone().fold(f64::INFINITY, f64::min);
+            let max_x = xs.fold(f64::NEG_INFINITY, f64::max);
+            let max_y = max_of(series.iter().flat_map(|s| s.values.iter().map(|p| p.y)));
+            let frame = Frame::new(1, max_y);
+            let span = (max_x - min_x).max(1.0);
+            let to_x = |x: f64| MARGIN_LEFT + (x - min_x) / span * frame.width;
+            // Label evenly spaced instants rather than categories
+            let ticks: Vec<(f64, String)> = (0..=4)
+                .map(|i| {
+                    let x = min_x + span * i as f64 / 4.0;
+                    (to_x(x), time_label(x, time_format))
+                })
+                .collect();
+            frame.y_axis(&mut svg, format_y);
+            for (x, label) in ticks {
+                frame.x_label(&mut svg, x, &label);
+            }
+            for (index, s) in series.iter().enumerate() {
+                let color = if series.len() == 1 {
+                    egui::Color32::LIGHT_BLUE
+                } else {
+                    series_color(index)
+                };
+                let points: Vec<String> = s
+                    .values
+                    .iter()
+                    .map(|p| format!("{:.1},{:.1}", to_x(p.x), frame.to_y(p.y)))
+                    .collect();
+                writeln!(
+                    svg,
+                    r#"<polyline fill="none" stroke="{}" stroke-width="1.5" points="{}"/>"#,
+                    hex(color),
+                    points.join(" ")
+                )
+                .ok();
+            }
+            legend(&mut svg, series.iter().map(|s| s.name.as_str()));
+        }
+        ProcessedChartData::Pie { slices } => donut(&mut svg, slices),
+        ProcessedChartData::Calendar { counts } => calendar(&mut svg, counts, year),
+        ProcessedChartData::Boxes { points, labels } => {
+            let frame = Frame::new(
+                labels.len(),
+                max_of(points.iter().map(|elem| elem.spread.upper_whisker)),
+            );
+            frame.axes(&mut svg, labels, format_y);
+            for (index, elem) in points.iter().enumerate() {
+                let spread = &elem.spread;
+                let center = frame.band_center(elem.argument);
+                let half = frame.band() * 0.3;
+                let color = hex(series_color(index));
+                writeln!(
+                    svg,
+                    r#"<line x1="{c:.1}" y1="{lo:.1}" x2="{c:.1}" y2="{hi:.1}" stroke="{color}"/><rect x="{x:.1}" y="{y:.1}" width="{w:.1}" height="{h:.1}" fill="{color}" fill-opacity="0.2" stroke="{color}"/><line x1="{x:.1}" y1="{m:.1}" x2="{x2:.1}" y2="{m:.1}" stroke="{color}" stroke-width="2"/>"#,
+                    c = center,
+                    lo = frame.to_y(spread.lower_whisker),
+                    hi = frame.to_y(spread.upper_whisker),
+                    x = center - half,
+                    x2 = center + half,
+                    y = frame.to_y(spread.quartile3),
+                    w = half * 2.0,
+                    h = frame.to_y(spread.quartile1) - frame.to_y(spread.quartile3),
+                    m = frame.to_y(spread.median),
+                )
+                .ok();
+            }
+        }
+        ProcessedChartData::None => {
+            writeln!(
+                svg,
+                r#"<text x="{}" y="{}" text-anchor="middle">No data available for this chart type.</text>"#,
+                SVG_WIDTH / 2.0,
+                SVG_HEIGHT / 2.0
+            )
+            .ok();
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn max_of(values: impl Iterator<Item = f64>) -> f64 {
+    let max = values.fold(0.0, f64::max);
+    if max > 0.0 { max } else { 1.0 }
+}
+
+/// The plotting area of a cartesian SVG chart with `categories` equal-width bands on the x axis.
+struct Frame {
+    categories: usize,
+    max_y: f64,
+    width: f64,
+    height: f64,
+}
+
+impl Frame {
+    fn new(categories: usize, max_y: f64) -> Self {
+        Self {
+            categories: categories.max(1),
+            max_y,
+            width: SVG_WIDTH - MARGIN_LEFT - MARGIN_RIGHT,
+            height: SVG_HEIGHT - MARGIN_TOP - MARGIN_BOTTOM,
+        }
+    }
+
+    fn band(&self) -> f64 {
+        self.width / self.categories as f64
+    }
+
+    fn band_center(&self, argument: f64) -> f64 {
+        MARGIN_LEFT + (argument + 0.5) * self.band()
+    }
+
+    fn to_y(&self, value: f64) -> f64 {
+        MARGIN_TOP + self.height * (1.0 - value / self.max_y)
+    }
+
+    fn y_axis(&self, svg: &mut String, format_y: fn(f64) -> String) {
+        for tick in 0..=4 {
+            let value = self.max_y * tick as f64 / 4.0;
+            let y = self.to_y(value);
+            writeln!(
+                svg,
+                r##"<line x1="{x1}" y1="{y:.1}" x2="{x2}" y2="{y:.1}" stroke="#dddddd"/><text x="{tx}" y="{y:.1}" text-anchor="end" dominant-baseline="middle">{label}</text>"##,
+                x1 = MARGIN_LEFT,
+                x2 = MARGIN_LEFT + self.width,
+                tx = MARGIN_LEFT - 6.0,
+                label = escape(&format_y(value)),
+            )
+            .ok();
+        }
+    }
+
+    fn x_label(&self, svg: &mut String, x: f64, label: &str) {
+        writeln!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}</text>"#,
+            x,
+            MARGIN_TOP + self.height + 16.0,
+            escape(label)
+        )
+        .ok();
+    }
+
+    /// Draws the y axis and a label under every n-th category.
+    fn axes(&self, svg: &mut String, labels: &[String], format_y: fn(f64) -> String) {
+        self.y_axis(svg, format_y);
+        let step = labels.len().div_ceil(MAX_X_LABELS).max(1);
+        for (index, label) in labels.iter().enumerate().step_by(step) {
+            self.x_label(svg, self.band_center(index as f64), label);
+        }
+    }
+
+    fn bar(&self, svg: &mut String, argument: f64, bottom: f64, value: f64, color: egui::Color32) {
+        let top = self.to_y(bottom + value);
+        writeln!(
+            svg,
+            r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}"/>"#,
+            self.band_center(argument) - self.band() * 0.4,
+            top,
+            self.band() * 0.8,
+            self.to_y(bottom) - top,
+            hex(color)
+        )
+        .ok();
+    }
+}
+
+fn legend<'a>(svg: &mut String, names: impl Iterator<Item = &'a str>) {
+    let x = SVG_WIDTH - MARGIN_RIGHT + 16.0;
+    for (index, name) in names.enumerate() {
+        let y = MARGIN_TOP + index as f64 * 18.0;
+        writeln!(
+            svg,
+            r#"<rect x="{x}" y="{y}" width="10" height="10" fill="{}"/><text x="{tx}" y="{ty}">{}</text>"#,
+            hex(series_color(index)),
+            escape(name),
+            tx = x + 16.0,
+            ty = y + 9.0,
+        )
+        .ok();
+    }
+}
+
+/// Draws a donut with one arc per slice, matching the layout of the plot.
+fn donut(svg: &mut String, slices: &[(String, f64)]) {
+    let total: f64 = slices.iter().map(|(_, value)| value).sum();
+    if total <= 0.0 {
+        return;
+    }
+    let center = (
+        MARGIN_LEFT + (SVG_WIDTH - MARGIN_LEFT - MARGIN_RIGHT) / 2.0,
+        MARGIN_TOP + (SVG_HEIGHT - MARGIN_TOP - MARGIN_BOTTOM) / 2.0,
+    );
+    let outer = (SVG_HEIGHT - MARGIN_TOP - MARGIN_BOTTOM) / 2.0;
+    let inner = outer / 2.0;
+    // SVG y grows downwards, so clockwise from twelve o'clock is increasing angle from -90°
+    let point = |radius: f64, angle: f64| {
+        (
+            center.0 + radius * angle.cos(),
+            center.1 + radius * angle.sin(),
+        )
+    };
+    let mut start = -FRAC_PI_2;
+    for (index, (_, value)) in slices.iter().enumerate() {
+        // A full circle would have identical end points, which SVG arcs can't draw
+        let sweep = (value / total * TAU).min(TAU - 1e-4);
+        let end = start + sweep;
+        let large = if sweep > std::f64::consts::PI { 1 } else { 0 };
+        let (ax, ay) = point(outer, start);
+        let (bx, by) = point(outer, end);
+        let (cx, cy) = point(inner, end);
+        let (dx, dy) = point(inner, start);
+        writeln!(
+            svg,
+            r#"<path d="M {ax:.2} {ay:.2} A {outer} {outer} 0 {large} 1 {bx:.2} {by:.2} L {cx:.2} {cy:.2} A {inner} {inner} 0 {large} 0 {dx:.2} {dy:.2} Z" fill="{}"/>"#,
+            hex(series_color(index))
+        )
+        .ok();
+        start = end;
+    }
+    let labels: Vec<String> = slices
+        .iter()
+        .map(|(name, value)| format!("{} ({:.1}%)", name, value / total * 100.0))
+        .collect();
+    legend(svg, labels.iter().map(String::as_str));
+}
+
+/// Draws one year of the calendar heatmap. Like the on-screen heatmap, a year without
+/// messages falls back to the latest year with messages.
+fn calendar(svg: &mut String, counts: &BTreeMap<NaiveDate, usize>, year: Option<i32>) {
+    const CELL: f64 = 11.0;
+    const GAP: f64 = 2.0;
+
+    let Some(latest) = counts.keys().next_back().map(|day| day.year()) else {
+        return;
+    };
+    let year = match year {
+        Some(year) if counts.keys().any(|day| day.year() == year) => year,
+        _ => latest,
+    };
+    let (Some(first_day), Some(last_day)) = (
+        NaiveDate::from_ymd_opt(year, 1, 1),
+        NaiveDate::from_ymd_opt(year, 12, 31),
+    ) else {
+        return;
+    };
+    let grid_start = first_day - Duration::days(first_day.weekday().num_days_from_monday() as i64);
+    let max_count = counts
+        .iter()
+        .filter(|(day, _)| day.year() == year)
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or(0);
+
+    let origin = (MARGIN_LEFT, MARGIN_TOP + 20.0);
+    writeln!(
+        svg,
+        r#"<text x="{}" y="{}">{}</text>"#,
+        origin.0,
+        MARGIN_TOP + 4.0,
+        year
+    )
+    .ok();
+    let mut day = first_day;
+    while day <= last_day {
+        let offset = (day - grid_start).num_days();
+        let count = *counts.get(&day).unwrap_or(&0);
+        writeln!(
+            svg,
+            r#"<rect x="{:.1}" y="{:.1}" width="{CELL}" height="{CELL}" rx="2" fill="{}"><title>{}: {}</title></rect>"#,
+            origin.0 + (offset / 7) as f64 * (CELL + GAP),
+            origin.1 + (offset % 7) as f64 * (CELL + GAP),
+            hex(heatmap_color(count, max_count, false)),
+            day,
+            count
+        )
+        .ok();
+        day += Duration::days(1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ChartRow;
+    use super::calendar;
+    use super::file_stem;
+    use super::to_csv;
+    use crate::charts::ProcessedChartData;
+    use crate::charts::Series;
+    use crate::time_bucket::TimeBucket;
+    use egui_plot::Bar;
+
+    #[test]
+    fn stacked_bars_flatten_to_labelled_rows() {
+        let data = ProcessedChartData::StackedBars {
+            series: vec![Series {
+                name: "gpt, \"4o\"".to_string(),
+                values: vec![Bar::new(0.0, 2.0), Bar::new(1.0, 3.0)],
+            }],
+            labels: vec!["2025-01".to_string(), "2025-02".to_string()],
+        };
+        let rows = data.rows(TimeBucket::Month.label_format());
+        assert_eq!(
+            rows[1],
+            ChartRow {
+                series: "gpt, \"4o\"".to_string(),
+                label: "2025-02".to_string(),
+                value: 3.0,
+            }
+        );
+        let csv = String::from_utf8(to_csv(&rows).unwrap()).unwrap();
+        assert_eq!(
+            csv,
+            "series,label,value\n\"gpt, \"\"4o\"\"\",2025-01,2.0\n\"gpt, \"\"4o\"\"\",2025-02,3.0\n"
+        );
+    }
+
+    #[test]
+    fn calendar_falls_back_to_a_year_with_messages() {
+        let day = chrono::NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let counts = std::collections::BTreeMap::from([(day, 3)]);
+        let mut svg = String::new();
+        calendar(&mut svg, &counts, Some(2025));
+        assert!(svg.contains(">2024</text>"));
+        assert!(svg.contains("2024-03-01: 3"));
+    }
+
+    #[test]
+    fn file_stems_are_slugs() {
+        assert_eq!(
+            file_stem("Messages per Month by Model"),
+            "messages-per-month-by-model"
+        );
+    }
+}
@@ -5,33 +5,71 @@ use egui::Ui;
 // Removed unused imports CoordinatesFormatter, Corner
 use egui_plot::Bar;
 use egui_plot::BarChart;
+use egui_plot::BoxElem;
+use egui_plot::BoxPlot;
+use egui_plot::BoxSpread;
 use egui_plot::Legend;
 use egui_plot::Line;
 use egui_plot::Plot;
 use egui_plot::PlotPoint;
 use egui_plot::PlotPoints;
+use egui_plot::Polygon;
 use egui_plot::Text;
 use tracing::trace;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 // Added DateTime back, removed unused NaiveDateTime, NaiveTime (still used in process_*)
 use chrono::DateTime;
 use chrono::Datelike;
 use chrono::Duration;
+use chrono::NaiveDate;
 use chrono::NaiveDateTime;
-use chrono::NaiveTime;
 use chrono::Timelike;
 use chrono::Utc;
+use chrono::Weekday;
 use tracing::info; // Needed for formatter signature
+use tracing::warn;
 
+use crate::chart_export::ExportFile;
+use crate::chart_export::ExportFormat;
+use crate::chart_export::file_stem;
+use crate::chart_export::format_number;
+use crate::chart_export::to_csv;
+use crate::chart_export::to_json;
+use crate::chart_export::to_png;
+use crate::chart_export::to_svg;
+use crate::filter::DrillDown;
+use crate::filter::ThreadFilter;
+use crate::latency::ResponseLatency;
+use crate::latency::response_latencies;
+use crate::line_mode::LineMode;
+use crate::model_params::ReasoningEffort;
+use crate::stats::format_seconds;
+use crate::stats::quantile;
+use crate::t3_json::T3Json;
 use crate::t3_json::T3Message;
+use crate::time_bucket::DateRange;
+use crate::time_bucket::TimeBucket;
+use crate::time_bucket::bucket_counts;
+use crate::timezone::DisplayTimezone;
 
 /// Enum to define the available quick chart views.
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)] // Add Hash for HashMap key
 pub enum ChartType {
     DayOfWeek,
-    Last30Days,
-    Last12Months,
+    Timeline,
     TimeOfDay,
+    ModelsByMonth,
+    ModelShare,
+    ReasoningEffortShare,
+    ModelsOverTime,
+    CalendarHeatmap,
+    LatencyByModel,
+    LatencyOverTime,
 }
 
 impl ChartType {
@@ -39,9 +77,15 @@ impl ChartType {
     pub fn name(&self) -> &'static str {
         match self {
             ChartType::DayOfWeek => "Message Volume by Day of Week",
-            ChartType::Last30Days => "Message Volume Last 30 Days",
-            ChartType::Last12Months => "Message Volume Last 12 Months",
+            ChartType::Timeline => "Message Volume over Time",
             ChartType::TimeOfDay => "Message Volume by Time of Day",
+            ChartType::ModelsByMonth => "Messages per Month by Model",
+            ChartType::ModelShare => "Share of Messages by Model",
+            ChartType::ReasoningEffortShare => "Share of Messages by Reasoning Effort",
+            ChartType::ModelsOverTime => "Models over Time",
+            ChartType::CalendarHeatmap => "Activity Calendar",
+            ChartType::LatencyByModel => "Response Latency by Model",
+            ChartType::LatencyOverTime => "Median Response Latency over Time",
         }
     }
 
@@ -49,9 +93,15 @@ impl ChartType {
     pub fn all() -> &'static [ChartType] {
         &[
             ChartType::DayOfWeek,
-            ChartType::Last30Days,
-            ChartType::Last12Months,
+            ChartType::Timeline,
             ChartType::TimeOfDay,
+            ChartType::ModelsByMonth,
+            ChartType::ModelShare,
+            ChartType::ReasoningEffortShare,
+            ChartType::ModelsOverTime,
+            ChartType::CalendarHeatmap,
+            ChartType::LatencyByModel,
+            ChartType::LatencyOverTime,
         ]
     }
 }
@@ -63,67 +113,113 @@ pub struct ChartState {
     /// Cache for processed chart data. Storing data that egui_plot can use directly.
     /// Using 'static lifetime as PlotPoints created from Vec<[f64; 2]> should own their data.
     processed_data: HashMap<ChartType, ProcessedChartData>, // No lifetime here
+    /// The filter the cached data was computed for. The cache is cleared when it changes.
+    processed_filter: ThreadFilter,
+    /// The settings the cached data was computed for. The cache is cleared when they change.
+    processed_settings: ChartSettings,
+    /// Date range and bucket size applied to every chart.
+    pub settings: ChartSettings,
+    /// Year shown by the calendar heatmap. Defaults to the most recent year with messages.
+    pub heatmap_year: Option<i32>,
+    /// How line charts are displayed. Applied after caching, so switching is instant.
+    pub line_mode: LineMode,
+    /// Screen area of the last drawn chart, used to crop PNG exports.
+    chart_rect: Option<egui::Rect>,
+    /// A finished export waiting to be saved.
+    export: Option<ExportFile>,
+    /// Identifies this state in `ChartJob`s, so results find their way back.
+    id: u64,
+    /// Charts being computed in the background for the current filter and settings.
+    pending: HashSet<ChartType>,
+    /// Jobs queued but not yet handed out by `take_jobs`.
+    jobs: Vec<ChartJob>,
 }
 
-/// Structure to hold the processed data for drawing a chart.
-#[derive(Clone)]
-pub enum ProcessedChartData {
-    Bars {
-        points: Vec<Bar>,
-        labels: Vec<String>,
-    }, // For categorical data like Day of Week
-    Lines {
-        points: Vec<PlotPoint>,
-    }, // Explicitly static lifetime for owned data
-    None, // No data or error in processing
+/// Source of `ChartState` ids.
+static NEXT_CHART_STATE_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A chart to compute in the background, with everything needed besides the export itself.
+pub struct ChartJob {
+    pub state_id: u64,
+    pub chart_type: ChartType,
+    pub filter: ThreadFilter,
+    pub settings: ChartSettings,
 }
 
-impl ChartState {
-    // No lifetime on impl
-    /// Creates a new ChartState with a default selected chart.
-    pub fn new() -> Self {
-        Self {
-            selected_chart: ChartType::DayOfWeek, // Default view
-            processed_data: HashMap::new(),
-        }
-    }
+impl ChartJob {
+    /// Processes the messages of the export that pass the filter and fall into the range.
+    pub fn run(&self, t3_json: &T3Json) -> ProcessedChartData {
+        let settings = &self.settings;
+        let messages = self.filter.apply(t3_json).messages;
+        let Some(range) = settings
+            .range
+            .or_else(|| DateRange::of_messages(&messages, settings.timezone))
+        else {
+            return ProcessedChartData::None;
+        };
+        let messages: Vec<&T3Message> = messages
+            .into_iter()
+            .filter(|m| range.contains(settings.timezone.naive(*m.created_at).date()))
+            .collect();
+        let messages = messages.as_slice();
 
-    /// Processes the raw messages data to generate plot points for a specific chart type.
-    /// Caches the result.
-    /// Note: The messages slice here only provides data for processing. The resulting
-    /// PlotPoints and Bars should own their data ('static lifetime).
-    fn process_messages(
-        &mut self,
-        chart_type: ChartType,
-        messages: &[T3Message],
-    ) -> ProcessedChartData {
-        // Check cache first
-        if let Some(data) = self.processed_data.get(&chart_type) {
-            trace!("Chart data for {:?} found in cache.", chart_type);
-            return data.clone();
+        match self.chart_type {
+            ChartType::DayOfWeek => settings.process_day_of_week(messages),
+            ChartType::Timeline => settings.process_timeline(messages, &range),
+            ChartType::TimeOfDay => settings.process_time_of_day(messages),
+            ChartType::ModelsByMonth => settings.process_models_by_month(messages, &range),
+            ChartType::ModelShare => settings.process_model_share(messages),
+            ChartType::ReasoningEffortShare => settings.process_reasoning_effort_share(messages),
+            ChartType::ModelsOverTime => settings.process_models_over_time(messages, &range),
+            ChartType::CalendarHeatmap => settings.process_calendar(messages),
+            ChartType::LatencyByModel => settings.process_latency_by_model(messages),
+            ChartType::LatencyOverTime => settings.process_latency_over_time(messages, &range),
         }
+    }
+}
 
-        info!("Processing chart data for {:?} (not in cache).", chart_type);
+/// A PNG export waiting for its screenshot.
+#[derive(Clone)]
+struct ChartScreenshot {
+    /// Id of the `Ui` the chart was drawn in, so each file's chart only takes its own screenshots.
+    id: egui::Id,
+    rect: egui::Rect,
+    file_name: String,
+}
 
-        let processed_data = match chart_type {
-            ChartType::DayOfWeek => self.process_day_of_week(messages),
-            ChartType::Last30Days => self.process_last_n_days(messages, 30),
-            ChartType::Last12Months => self.process_last_n_months(messages, 12),
-            ChartType::TimeOfDay => self.process_time_of_day(messages),
-        };
+/// User-selected options that change how messages are aggregated.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChartSettings {
+    /// Only messages within this range are charted. `None` uses the span of the messages.
+    pub range: Option<DateRange>,
+    /// Bucket size for time series charts.
+    pub bucket: TimeBucket,
+    /// Timezone messages are bucketed in, so days and hours match the viewer's wall clock.
+    pub timezone: DisplayTimezone,
+}
 
-        // Store in cache
-        self.processed_data
-            .insert(chart_type, processed_data.clone());
-        processed_data
+impl Default for ChartSettings {
+    fn default() -> Self {
+        Self {
+            range: None,
+            bucket: TimeBucket::Day,
+            timezone: DisplayTimezone::default(),
+        }
     }
-    fn process_day_of_week(&self, messages: &[T3Message]) -> ProcessedChartData {
+}
+
+impl ChartSettings {
+    fn process_day_of_week(&self, messages: &[&T3Message]) -> ProcessedChartData {
         // Map Sunday=0...Saturday=6 to Monday=0...Sunday=6 for typical charting
         let day_order = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
         let mut counts = HashMap::new();
 
         for message in messages {
-            let weekday = message.created_at.weekday().num_days_from_monday() as usize;
+            let weekday = self
+                .timezone
+                .naive(*message.created_at)
+                .weekday()
+                .num_days_from_monday() as usize;
             *counts.entry(weekday).or_insert(0) += 1;
         }
 
@@ -132,13 +228,14 @@ impl ChartState {
         // a custom axis formatter on the Plot.
 
         // Generate data for all 7 days, even if count is 0
-        for i in 0..7 {
+        for (i, day) in day_order.iter().enumerate() {
             let count = *counts.get(&i).unwrap_or(&0);
             // Use `i` as the x-coordinate, counts as y.
             bars.push(
                 Bar::new(i as f64, count as f64)
+                    .width(BAR_WIDTH)
                     // Optional: Add names to bars if needed for legend
-                    .name(day_order[i]),
+                    .name(day),
             );
         }
 
@@ -159,123 +256,36 @@ impl ChartState {
         } // Keep labels here for the formatter to access easily
     }
 
-    /// Aggregates messages for the last N days.
-    /// This processes all messages and then filters to the last N days.
-    /// For Line chart, we need points sorted by date.
-    fn process_last_n_days(&self, messages: &[T3Message], n: usize) -> ProcessedChartData {
-        let now_date = Utc::now().date_naive(); // Use NaiveDate for date comparison
-
-        let mut counts_by_date: HashMap<NaiveDateTime, usize> = HashMap::new();
-
-        for message in messages {
-            let message_date_time = message.created_at.naive_utc(); // Use naive_utc
-            let message_date = message_date_time.date();
-
-            // Check if the message date is within the last N days (inclusive of today)
-            if message_date >= now_date - Duration::days(n as i64 - 1) && message_date <= now_date {
-                // Bin by day (strip time component for counting)
-                let day_start = message_date.and_time(NaiveTime::from_hms_opt(0, 0, 0).unwrap());
-                *counts_by_date.entry(day_start).or_insert(0) += 1;
-            }
+    /// Counts messages per bucket over the range, one point per bucket including empty ones.
+    fn process_timeline(&self, messages: &[&T3Message], range: &DateRange) -> ProcessedChartData {
+        if messages.is_empty() {
+            return ProcessedChartData::None;
         }
 
-        // Collect dates and counts, then sort by date
-        let mut dated_counts: Vec<(NaiveDateTime, usize)> = counts_by_date.into_iter().collect();
-        dated_counts.sort_by_key(|(date, _)| *date);
-
         // Convert to PlotPoints (using seconds since epoch for flexibility)
-        let points: Vec<[f64; 2]> = dated_counts
+        let points = bucket_counts(messages, range, self.bucket, self.timezone)
             .into_iter()
-            .map(|(date, count)| {
-                // Convert NaiveDateTime to seconds since epoch as f64
-                // Use and_utc().timestamp() as suggested by compiler warning
-                let seconds_since_epoch = date.and_utc().timestamp() as f64;
-                [seconds_since_epoch, count as f64]
+            .map(|(start, count)| {
+                let seconds_since_epoch = start.and_utc().timestamp() as f64;
+                PlotPoint::new(seconds_since_epoch, count as f64)
             })
             .collect();
 
-        if points.is_empty() {
-            ProcessedChartData::None
-        } else {
-            ProcessedChartData::Lines {
-                points: points.into_iter().map(|point| point.into()).collect(),
-            }
-        }
-    }
-
-    /// Aggregates messages for the last 12 months.
-    /// Similar to last N days, but bins by month.
-    fn process_last_n_months(&self, messages: &[T3Message], n: usize) -> ProcessedChartData {
-        let now = Utc::now();
-        // Calculate the start date for N months ago.
-        // Chrono's Duration doesn't handle months well due to varying lengths.
-        // A more robust way is to work with years and months.
-        let mut start_date = now.date_naive();
-        let mut current_month_start = start_date.with_day(1).unwrap_or(start_date);
-
-        for _ in 0..(n - 1) {
-            // Subtract n-1 months to get the start of the Nth month ago
-            current_month_start = if current_month_start.month() == 1 {
-                current_month_start
-                    .with_year(current_month_start.year() - 1)
-                    .unwrap_or(current_month_start)
-                    .with_month(12)
-                    .unwrap_or(current_month_start)
-            } else {
-                current_month_start
-                    .with_month(current_month_start.month() - 1)
-                    .unwrap_or(current_month_start)
-            };
-        }
-        start_date = current_month_start;
-
-        let mut counts_by_month: HashMap<NaiveDateTime, usize> = HashMap::new();
-
-        for message in messages {
-            let message_date_time = message.created_at.naive_utc();
-
-            // Check if the message is within the last N months (inclusive of the start month)
-            if message_date_time.date() >= start_date {
-                // Bin by month (start of the month)
-                let month_start = message_date_time
-                    .date()
-                    .with_day(1)
-                    .unwrap_or(message_date_time.date())
-                    .and_time(NaiveTime::from_hms_opt(0, 0, 0).unwrap());
-                *counts_by_month.entry(month_start).or_insert(0) += 1;
-            }
-        }
-
-        // Collect dates and counts, then sort by date
-        let mut dated_counts: Vec<(NaiveDateTime, usize)> = counts_by_month.into_iter().collect();
-        dated_counts.sort_by_key(|(date, _)| *date);
-
-        // Convert to PlotPoints (using seconds since epoch for flexibility)
-        let points: Vec<[f64; 2]> = dated_counts
-            .into_iter()
-            .map(|(date, count)| {
-                // Convert NaiveDateTime to seconds since epoch as f64
-                let seconds_since_epoch = date.and_utc().timestamp() as f64;
-                [seconds_since_epoch, count as f64]
-            })
-            .collect();
-
-        if points.is_empty() {
-            ProcessedChartData::None
-        } else {
-            ProcessedChartData::Lines {
-                points: points.into_iter().map(|point| point.into()).collect(),
-            }
+        ProcessedChartData::Lines {
+            series: vec![Series {
+                name: "Messages".to_string(),
+                values: points,
+            }],
         }
     }
 
     /// Aggregates messages by time of day, binned by 30 minutes.
-    fn process_time_of_day(&self, messages: &[T3Message]) -> ProcessedChartData {
+    fn process_time_of_day(&self, messages: &[&T3Message]) -> ProcessedChartData {
         let mut counts_by_bin = HashMap::new();
         let _bin_duration = Duration::minutes(30); // This variable was unused, prefix with _
 
         for message in messages {
-            let time = message.created_at.time();
+            let time = self.timezone.naive(*message.created_at).time();
             // Calculate the 30-minute bin index (0-47)
             // Hours * 2 + (minutes / 30)
             let bin_index = (time.hour() * 2 + time.minute() / 30) as usize;
@@ -289,7 +299,7 @@ impl ChartState {
         for bin_index in 0..48 {
             let count = *counts_by_bin.get(&bin_index).unwrap_or(&0);
             // Use bin_index as the x-coordinate
-            bars.push(Bar::new(bin_index as f64, count as f64));
+            bars.push(Bar::new(bin_index as f64, count as f64).width(BAR_WIDTH));
 
             // Generate time label for the bin start
             let hour = bin_index / 2;
@@ -306,14 +316,458 @@ impl ChartState {
         }
     }
 
+    /// Counts messages per month, split by model, as stacked bars.
+    fn process_models_by_month(
+        &self,
+        messages: &[&T3Message],
+        range: &DateRange,
+    ) -> ProcessedChartData {
+        if messages.is_empty() {
+            return ProcessedChartData::None;
+        }
+
+        let months = TimeBucket::Month.starts(range);
+        let series = group_by_model(messages)
+            .into_iter()
+            .map(|model| {
+                let bars = bucket_counts(&model.values, range, TimeBucket::Month, self.timezone)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, (_, count))| {
+                        Bar::new(index as f64, count as f64)
+                            .width(BAR_WIDTH)
+                            .name(&model.name)
+                    })
+                    .collect();
+                Series {
+                    name: model.name,
+                    values: bars,
+                }
+            })
+            .collect();
+
+        ProcessedChartData::StackedBars {
+            series,
+            labels: months
+                .iter()
+                .map(|month| month.format("%Y-%m").to_string())
+                .collect(),
+        }
+    }
+
+    /// Computes each model's share of all messages.
+    fn process_model_share(&self, messages: &[&T3Message]) -> ProcessedChartData {
+        let slices: Vec<(String, f64)> = group_by_model(messages)
+            .into_iter()
+            .map(|model| (model.name, model.values.len() as f64))
+            .collect();
+
+        if slices.is_empty() {
+            ProcessedChartData::None
+        } else {
+            ProcessedChartData::Pie { slices }
+        }
+    }
+
+    /// Computes each reasoning effort's share of the messages sent with model parameters.
+    /// Messages without parameters are left out, so exports that only record them on some
+    /// messages aren't dominated by "not set".
+    fn process_reasoning_effort_share(&self, messages: &[&T3Message]) -> ProcessedChartData {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for message in messages {
+            if let Some(params) = &message.model_params {
+                *counts
+                    .entry(ReasoningEffort::label(params.reasoning_effort.as_ref()))
+                    .or_default() += 1;
+            }
+        }
+        let mut slices: Vec<(String, f64)> = counts
+            .into_iter()
+            .map(|(effort, count)| (effort.to_string(), count as f64))
+            .collect();
+        slices.sort_by(|(a_name, a), (b_name, b)| b.total_cmp(a).then(a_name.cmp(b_name)));
+
+        if slices.is_empty() {
+            ProcessedChartData::None
+        } else {
+            ProcessedChartData::Pie { slices }
+        }
+    }
+
+    /// Counts messages per bucket with one line per model.
+    fn process_models_over_time(
+        &self,
+        messages: &[&T3Message],
+        range: &DateRange,
+    ) -> ProcessedChartData {
+        if messages.is_empty() {
+            return ProcessedChartData::None;
+        }
+
+        let series = group_by_model(messages)
+            .into_iter()
+            .map(|model| {
+                let points = bucket_counts(&model.values, range, self.bucket, self.timezone)
+                    .into_iter()
+                    .map(|(start, count)| {
+                        let seconds_since_epoch = start.and_utc().timestamp() as f64;
+                        PlotPoint::new(seconds_since_epoch, count as f64)
+                    })
+                    .collect();
+                Series {
+                    name: model.name,
+                    values: points,
+                }
+            })
+            .collect();
+
+        ProcessedChartData::Lines { series }
+    }
+
+    /// Summarises response latencies per responding model as box plots.
+    /// Whiskers extend to the furthest latency within 1.5 IQR of the box.
+    fn process_latency_by_model(&self, messages: &[&T3Message]) -> ProcessedChartData {
+        let latencies = response_latencies(messages);
+        let latencies: Vec<&ResponseLatency> = latencies.iter().collect();
+
+        let mut points = vec![];
+        let mut labels = vec![];
+        let by_model = group_by_model_of(&latencies, |latency| latency.response.model.as_str());
+        for (index, model) in by_model.into_iter().enumerate() {
+            let mut seconds: Vec<f64> = model.values.iter().map(|l| l.seconds).collect();
+            seconds.sort_by(f64::total_cmp);
+            let q1 = quantile(&seconds, 0.25);
+            let q3 = quantile(&seconds, 0.75);
+            let fence = 1.5 * (q3 - q1);
+            let lower = seconds
+                .iter()
+                .copied()
+                .find(|s| *s >= q1 - fence)
+                .unwrap_or(q1);
+            let upper = seconds
+                .iter()
+                .rev()
+                .copied()
+                .find(|s| *s <= q3 + fence)
+                .unwrap_or(q3);
+            let spread = BoxSpread::new(lower, q1, quantile(&seconds, 0.5), q3, upper);
+            points.push(
+                BoxElem::new(index as f64, spread)
+                    .box_width(BOX_WIDTH)
+                    .name(&model.name)
+                    .fill(series_color(index).linear_multiply(0.2))
+                    .stroke(egui::Stroke::new(1.0, series_color(index))),
+            );
+            labels.push(model.name);
+        }
+
+        if points.is_empty() {
+            ProcessedChartData::None
+        } else {
+            ProcessedChartData::Boxes { points, labels }
+        }
+    }
+
+    /// Computes the median response latency per bucket, with one line per responding model.
+    /// Buckets are assigned by the time of the prompt; buckets without responses are skipped.
+    fn process_latency_over_time(
+        &self,
+        messages: &[&T3Message],
+        range: &DateRange,
+    ) -> ProcessedChartData {
+        let latencies = response_latencies(messages);
+        let latencies: Vec<&ResponseLatency> = latencies.iter().collect();
+
+        let series: Vec<Series<PlotPoint>> =
+            group_by_model_of(&latencies, |latency| latency.response.model.as_str())
+                .into_iter()
+                .map(|model| {
+                    let mut by_bucket: BTreeMap<NaiveDateTime, Vec<f64>> = BTreeMap::new();
+                    for latency in &model.values {
+                        let prompt_at = self.timezone.naive(*latency.prompt.created_at);
+                        if range.contains(prompt_at.date()) {
+                            by_bucket
+                                .entry(self.bucket.floor(prompt_at))
+                                .or_default()
+                                .push(latency.seconds);
+                        }
+                    }
+                    let points = by_bucket
+                        .into_iter()
+                        .map(|(start, mut seconds)| {
+                            seconds.sort_by(f64::total_cmp);
+                            PlotPoint::new(
+                                start.and_utc().timestamp() as f64,
+                                quantile(&seconds, 0.5),
+                            )
+                        })
+                        .collect();
+                    Series {
+                        name: model.name,
+                        values: points,
+                    }
+                })
+                .collect();
+
+        if series.is_empty() {
+            ProcessedChartData::None
+        } else {
+            ProcessedChartData::Lines { series }
+        }
+    }
+
+    /// Counts messages per calendar day.
+    fn process_calendar(&self, messages: &[&T3Message]) -> ProcessedChartData {
+        let mut counts = BTreeMap::new();
+        for message in messages {
+            let day = self.timezone.naive(*message.created_at).date();
+            *counts.entry(day).or_insert(0) += 1;
+        }
+
+        if counts.is_empty() {
+            ProcessedChartData::None
+        } else {
+            ProcessedChartData::Calendar { counts }
+        }
+    }
+}
+
+/// Number of models shown individually in the per-model charts; the rest are grouped as "Other".
+const MAX_MODEL_SERIES: usize = 8;
+/// Width of bars, with one bar per unit of x.
+const BAR_WIDTH: f64 = 0.5;
+/// Width of the latency boxes, with one box per unit of x.
+const BOX_WIDTH: f64 = 0.25;
+
+/// A named series of values, drawn with its own legend entry.
+#[derive(Clone)]
+pub struct Series<T> {
+    pub name: String,
+    pub values: Vec<T>,
+}
+
+/// Structure to hold the processed data for drawing a chart.
+#[derive(Clone)]
+pub enum ProcessedChartData {
+    Bars {
+        points: Vec<Bar>,
+        labels: Vec<String>,
+    }, // For categorical data like Day of Week
+    StackedBars {
+        series: Vec<Series<Bar>>,
+        labels: Vec<String>,
+    }, // One bar chart per series, stacked on top of the previous ones
+    Lines {
+        series: Vec<Series<PlotPoint>>,
+    }, // Explicitly static lifetime for owned data
+    Pie {
+        slices: Vec<(String, f64)>,
+    }, // Drawn as a donut, one slice per entry
+    Calendar {
+        counts: BTreeMap<NaiveDate, usize>,
+    }, // Messages per day, drawn as a heatmap one year at a time
+    Boxes {
+        points: Vec<BoxElem>,
+        labels: Vec<String>,
+    }, // One box per category, e.g. latency distribution per model
+    None, // No data or error in processing
+}
+
+/// Returns the colour used for the series at `index`.
+/// Uses the same golden-ratio hue walk as egui_plot's automatic colours.
+pub(crate) fn series_color(index: usize) -> egui::Color32 {
+    let golden_ratio = (5.0_f32.sqrt() - 1.0) / 2.0;
+    let h = (index as f32 * golden_ratio).fract();
+    egui::ecolor::Hsva::new(h, 0.85, 0.5, 1.0).into()
+}
+
+/// Groups messages by model, keeping the busiest models and folding the rest into "Other".
+/// Series are ordered by descending message count.
+fn group_by_model<'a>(messages: &[&'a T3Message]) -> Vec<Series<&'a T3Message>> {
+    group_by_model_of(messages, |message| message.model.as_str())
+}
+
+/// Groups anything with a model, such as response latencies, like `group_by_model`.
+fn group_by_model_of<'a, T: Copy>(
+    items: &'a [T],
+    model: impl Fn(&'a T) -> &'a str,
+) -> Vec<Series<T>> {
+    let mut by_model: HashMap<&str, Vec<T>> = HashMap::new();
+    for item in items {
+        by_model.entry(model(item)).or_default().push(*item);
+    }
+    let mut by_model: Vec<(&str, Vec<T>)> = by_model.into_iter().collect();
+    by_model.sort_by(|(a_name, a), (b_name, b)| b.len().cmp(&a.len()).then(a_name.cmp(b_name)));
+
+    let mut series: Vec<Series<T>> = vec![];
+    let mut other = vec![];
+    for (index, (model, items)) in by_model.into_iter().enumerate() {
+        if index < MAX_MODEL_SERIES {
+            series.push(Series {
+                name: model.to_string(),
+                values: items,
+            });
+        } else {
+            other.extend(items);
+        }
+    }
+    if !other.is_empty() {
+        series.push(Series {
+            name: "Other".to_string(),
+            values: other,
+        });
+    }
+    series
+}
+
+impl Default for ChartState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChartState {
+    // No lifetime on impl
+    /// Creates a new ChartState with a default selected chart.
+    pub fn new() -> Self {
+        Self {
+            selected_chart: ChartType::DayOfWeek, // Default view
+            processed_data: HashMap::new(),
+            processed_filter: ThreadFilter::default(),
+            processed_settings: ChartSettings::default(),
+            settings: ChartSettings::default(),
+            heatmap_year: None,
+            line_mode: LineMode::default(),
+            chart_rect: None,
+            export: None,
+            id: NEXT_CHART_STATE_ID.fetch_add(1, Ordering::Relaxed),
+            pending: HashSet::new(),
+            jobs: vec![],
+        }
+    }
+
+    /// Returns the cached data for a chart, or queues a job to compute it and returns `None`.
+    /// Changing the filter or settings clears the cache; results of outdated jobs are dropped.
+    fn chart_data(
+        &mut self,
+        chart_type: ChartType,
+        filter: &ThreadFilter,
+    ) -> Option<ProcessedChartData> {
+        if self.processed_filter != *filter || self.processed_settings != self.settings {
+            trace!("Filter or settings changed, clearing chart cache.");
+            self.processed_data.clear();
+            self.pending.clear();
+            self.processed_filter = filter.clone();
+            self.processed_settings = self.settings.clone();
+        }
+
+        // Check cache first
+        if let Some(data) = self.processed_data.get(&chart_type) {
+            trace!("Chart data for {:?} found in cache.", chart_type);
+            return Some(data.clone());
+        }
+
+        if self.pending.insert(chart_type) {
+            info!("Queueing chart data for {:?} (not in cache).", chart_type);
+            self.jobs.push(ChartJob {
+                state_id: self.id,
+                chart_type,
+                filter: filter.clone(),
+                settings: self.settings.clone(),
+            });
+        }
+        None
+    }
+
+    /// Returns the jobs queued since the last call, for the caller to run in the background.
+    pub fn take_jobs(&mut self) -> Vec<ChartJob> {
+        std::mem::take(&mut self.jobs)
+    }
+
+    /// Returns the id that `ChartJob::state_id` refers to.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Stores the result of a job, unless the filter or settings changed since it was queued.
+    pub fn receive(&mut self, job: ChartJob, data: ProcessedChartData) {
+        if job.filter != self.processed_filter || job.settings != self.processed_settings {
+            trace!("Dropping outdated chart data for {:?}.", job.chart_type);
+            return;
+        }
+        self.pending.remove(&job.chart_type);
+        self.processed_data.insert(job.chart_type, data);
+    }
+
     /// Draws the currently selected chart using egui_plot.
-    /// This will trigger processing if the data is not cached.
-    pub fn draw(&mut self, ui: &mut Ui, messages: &[T3Message]) {
-        // Process messages if needed and get the data
-        let chart_data = self.process_messages(self.selected_chart, messages);
+    /// `messages` must be the messages that pass `filter`.
+    /// If the data is not cached, a job is queued (see `take_jobs`) and a spinner is shown.
+    /// Returns the bucket the user clicked, if any, so the thread list can be narrowed to it.
+    pub fn draw(
+        &mut self,
+        ui: &mut Ui,
+        messages: &[&T3Message],
+        filter: &ThreadFilter,
+    ) -> Option<DrillDown> {
+        self.draw_settings(ui, messages);
+
+        // Get the data, or wait for the background job computing it
+        let Some(chart_data) = self.chart_data(self.selected_chart, filter) else {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(format!("Computing {}…", self.selected_chart.name()));
+            });
+            return None;
+        };
+        let chart_data = match chart_data {
+            ProcessedChartData::Lines { series } => ProcessedChartData::Lines {
+                series: self.line_mode().apply(&series),
+            },
+            chart_data => chart_data,
+        };
 
-        ui.label(format!("Showing: {}", self.selected_chart.name()));
+        let mut export = None;
+        ui.horizontal(|ui| {
+            ui.label(format!("Showing: {}", self.selected_chart.name()));
+            ui.separator();
+            ui.label("Export:");
+            for format in ExportFormat::all() {
+                if ui.small_button(format.extension().to_uppercase()).clicked() {
+                    export = Some(*format);
+                }
+            }
+        });
+        self.receive_screenshots(ui);
+
+        let drill_down = if let ProcessedChartData::Calendar { counts } = &chart_data {
+            self.draw_calendar(ui, counts)
+        } else {
+            let clicked = self.draw_plot(ui, &chart_data);
+            let range = self.chart_range(messages);
+            let drill_down = clicked
+                .zip(range)
+                .and_then(|(clicked, range)| self.drill_down_at(&chart_data, clicked, &range));
+            if let Some(drill_down) = &drill_down {
+                info!("Chart clicked: {:?}", drill_down);
+            }
+            drill_down
+        };
+        if let Some(format) = export {
+            self.start_export(ui, format, &chart_data);
+        }
+        drill_down
+    }
+
+    /// Returns the range charts are computed over: the selected one, or the span of the messages.
+    fn chart_range(&self, messages: &[&T3Message]) -> Option<DateRange> {
+        self.settings
+            .range
+            .or_else(|| DateRange::of_messages(messages, self.settings.timezone))
+    }
 
+    /// Draws bar, line, pie and box charts with egui_plot.
+    /// Returns the plot coordinates of a click, if any.
+    fn draw_plot(&mut self, ui: &mut Ui, chart_data: &ProcessedChartData) -> Option<PlotPoint> {
         let mut plot = Plot::new(format!("{}_plot", self.selected_chart.name()))
             .data_aspect(1.0) // Adjust aspect ratio as needed
             .legend(Legend::default());
@@ -322,7 +776,7 @@ impl ChartState {
         match self.selected_chart {
             ChartType::DayOfWeek => {
                 // Assuming ProcessedChartData::Bars contains the labels Vec for DayOfWeek
-                if let ProcessedChartData::Bars { labels, .. } = &chart_data {
+                if let ProcessedChartData::Bars { labels, .. } = chart_data {
                     let labels_clone = labels.clone(); // Clone labels for the closure
                     // Explicitly type closure arguments
                     plot = plot.x_axis_formatter(move |x, _range| {
@@ -335,29 +789,34 @@ impl ChartState {
                     });
                 }
             }
-            ChartType::Last30Days | ChartType::Last12Months => {
-                // Explicitly type closure arguments
-                plot = plot.x_axis_formatter(|x, _range| {
-                    // Basic formatter for seconds since epoch - can be improved
-                    // Use DateTime::from_timestamp
-                    if let Some(date_time) =
-                        DateTime::<Utc>::from_timestamp(x.value.round() as i64, 0)
-                    {
-                        // Format date appropriately
-                        if self.selected_chart == ChartType::Last30Days {
-                            date_time.format("%m-%d").to_string() // Month-Day for recent days
-                        } else {
-                            // Last12Months
-                            date_time.format("%Y-%m").to_string() // Year-Month for months
-                        }
-                    } else {
-                        "".to_string() // Invalid timestamp
-                    }
+            ChartType::Timeline | ChartType::ModelsOverTime | ChartType::LatencyOverTime => {
+                let format = self.line_mode().time_format(self.settings.bucket);
+                plot = plot.x_axis_formatter(move |x, _range| {
+                    // x is seconds since epoch of the bucket start
+                    DateTime::<Utc>::from_timestamp(x.value.round() as i64, 0)
+                        .map(|date_time| date_time.format(format).to_string())
+                        .unwrap_or_default()
                 });
             }
-            ChartType::TimeOfDay => {
-                // Assuming ProcessedChartData::Bars contains the labels Vec for TimeOfDay
-                if let ProcessedChartData::Bars { labels, .. } = &chart_data {
+            ChartType::ModelShare | ChartType::ReasoningEffortShare => {
+                // The donut is drawn in unit coordinates, so the axes carry no meaning
+                plot = plot.show_axes(false).show_grid(false);
+            }
+            ChartType::CalendarHeatmap => {} // Drawn without egui_plot when there is data
+            ChartType::LatencyByModel => {
+                if let ProcessedChartData::Boxes { labels, .. } = chart_data {
+                    let labels_clone = labels.clone();
+                    plot = plot.x_axis_formatter(move |x, _range| {
+                        let index = x.value.round() as usize;
+                        labels_clone.get(index).cloned().unwrap_or_default()
+                    });
+                }
+            }
+            ChartType::TimeOfDay | ChartType::ModelsByMonth => {
+                // Bars and StackedBars both carry one label per x index
+                if let ProcessedChartData::Bars { labels, .. }
+                | ProcessedChartData::StackedBars { labels, .. } = chart_data
+                {
                     let labels_clone = labels.clone(); // Clone labels for the closure
                     // Explicitly type closure arguments
                     plot = plot.x_axis_formatter(move |x, _range| {
@@ -372,8 +831,12 @@ impl ChartState {
             }
         }
 
-        plot.show(ui, |plot_ui| {
-            match &chart_data {
+        if self.is_latency_chart() {
+            plot = plot.y_axis_formatter(|y, _range| format_seconds(y.value.max(0.0)));
+        }
+
+        let response = plot.show(ui, |plot_ui| {
+            match chart_data {
                 ProcessedChartData::Bars { points, labels: _ } => {
                     // labels is not used here, only in formatter
                     if !points.is_empty() {
@@ -385,14 +848,50 @@ impl ChartState {
                         plot_ui.bar_chart(bar_chart);
                     }
                 }
-                ProcessedChartData::Lines { points } => {
-                    if !points.is_empty() {
+                ProcessedChartData::StackedBars { series, labels: _ } => {
+                    let mut charts: Vec<BarChart> = vec![];
+                    for (index, bars) in series.iter().enumerate() {
+                        let others: Vec<&BarChart> = charts.iter().collect();
+                        let chart = BarChart::new(&bars.name, bars.values.clone())
+                            .color(series_color(index))
+                            .stack_on(&others);
+                        charts.push(chart);
+                    }
+                    for chart in charts {
+                        plot_ui.bar_chart(chart);
+                    }
+                }
+                ProcessedChartData::Lines { series } => {
+                    let single = series.len() == 1;
+                    for (index, line) in series.iter().enumerate() {
                         // Line::new takes name and PlotPoints
-                        let line = Line::new("Messages", PlotPoints::Borrowed(points)) // points is PlotPoints
-                            .color(egui::Color32::LIGHT_BLUE);
-                        plot_ui.line(line);
+                        let color = if single {
+                            egui::Color32::LIGHT_BLUE
+                        } else {
+                            series_color(index)
+                        };
+                        plot_ui.line(
+                            Line::new(&line.name, PlotPoints::Borrowed(&line.values)).color(color),
+                        );
                     }
                 }
+                ProcessedChartData::Pie { slices } => {
+                    draw_donut(plot_ui, slices);
+                }
+                ProcessedChartData::Calendar { .. } => {} // Handled before the plot is built
+                ProcessedChartData::Boxes { points, labels: _ } => {
+                    plot_ui.box_plot(BoxPlot::new("Latency", points.clone()).element_formatter(
+                        Box::new(|elem, _plot| {
+                            format!(
+                                "{}\nMedian: {}\nQ1-Q3: {} - {}",
+                                elem.name,
+                                format_seconds(elem.spread.median),
+                                format_seconds(elem.spread.quartile1),
+                                format_seconds(elem.spread.quartile3),
+                            )
+                        }),
+                    ));
+                }
                 ProcessedChartData::None => {
                     // Use Text::new(position, text) or Text::new(name, position, text) depending on egui_plot version
                     // Let's check egui_plot 0.32.1 docs... it's Text::new(name, position, text)
@@ -403,6 +902,563 @@ impl ChartState {
                     )); // Name, Position, Text
                 }
             }
+            if plot_ui.response().clicked() {
+                plot_ui.pointer_coordinate()
+            } else {
+                None
+            }
         });
+        self.chart_rect = Some(response.response.rect);
+        response.inner
+    }
+
+    /// Works out which bar, point, slice or box was clicked and returns the matching drill-down.
+    /// Series named "Other" group several models, so clicking them ignores the model.
+    fn drill_down_at(
+        &self,
+        chart_data: &ProcessedChartData,
+        pointer: PlotPoint,
+        range: &DateRange,
+    ) -> Option<DrillDown> {
+        let model_of = |name: &str| (name != "Other").then(|| name.to_string());
+        // Index of the bar or box under the pointer; they are drawn at integer x, so clicks in
+        // the gaps between them miss
+        let half_width = match chart_data {
+            ProcessedChartData::Boxes { .. } => BOX_WIDTH / 2.0,
+            _ => BAR_WIDTH / 2.0,
+        };
+        let x = pointer.x.round();
+        let index = (x >= 0.0 && (x - pointer.x).abs() <= half_width).then_some(x as usize);
+
+        match (self.selected_chart, chart_data) {
+            (ChartType::DayOfWeek, ProcessedChartData::Bars { .. }) => {
+                let weekday = Weekday::try_from(u8::try_from(index?).ok()?).ok()?;
+                Some(DrillDown::Weekday {
+                    weekday,
+                    range: *range,
+                })
+            }
+            (ChartType::TimeOfDay, ProcessedChartData::Bars { points, .. }) => {
+                let bin = index.filter(|index| *index < points.len())?;
+                Some(DrillDown::TimeOfDay {
+                    bin: bin as u32,
+                    range: *range,
+                })
+            }
+            (ChartType::ModelsByMonth, ProcessedChartData::StackedBars { series, labels }) => {
+                let index = index?;
+                let start = *TimeBucket::Month.starts(range).get(index)?;
+                // Find the segment of the stack under the pointer
+                let mut bottom = 0.0;
+                let mut model = None;
+                for bars in series {
+                    let value = bars.values.get(index).map_or(0.0, |bar| bar.value);
+                    if bottom <= pointer.y && pointer.y < bottom + value {
+                        model = model_of(&bars.name);
+                    }
+                    bottom += value;
+                }
+                Some(DrillDown::Period {
+                    start,
+                    end: TimeBucket::Month.next(start),
+                    label: labels.get(index)?.clone(),
+                    model,
+                })
+            }
+            (
+                ChartType::Timeline | ChartType::ModelsOverTime | ChartType::LatencyOverTime,
+                ProcessedChartData::Lines { series },
+            ) => {
+                // Overlaid years share x values, so a point doesn't identify a bucket
+                if self.line_mode() == LineMode::YearOverYear {
+                    return None;
+                }
+                let x = series
+                    .iter()
+                    .flat_map(|line| line.values.iter().map(|point| point.x))
+                    .min_by(|a, b| (a - pointer.x).abs().total_cmp(&(b - pointer.x).abs()))?;
+                let start = DateTime::<Utc>::from_timestamp(x as i64, 0)?.naive_utc();
+                let model = if self.selected_chart == ChartType::Timeline {
+                    None
+                } else {
+                    series
+                        .iter()
+                        .filter_map(|line| {
+                            let point = line.values.iter().find(|point| point.x == x)?;
+                            Some((line, (point.y - pointer.y).abs()))
+                        })
+                        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                        .and_then(|(line, _)| model_of(&line.name))
+                };
+                let bucket = self.settings.bucket;
+                Some(DrillDown::Period {
+                    start,
+                    end: bucket.next(start),
+                    label: start.format(bucket.label_format()).to_string(),
+                    model,
+                })
+            }
+            (ChartType::ModelShare, ProcessedChartData::Pie { slices }) => Some(DrillDown::Model {
+                model: model_of(slice_at(slices, pointer)?)?,
+                range: *range,
+            }),
+            (ChartType::ReasoningEffortShare, ProcessedChartData::Pie { slices }) => {
+                let effort = ReasoningEffort::from_label(slice_at(slices, pointer)?);
+                Some(DrillDown::ReasoningEffort {
+                    effort,
+                    range: *range,
+                })
+            }
+            (ChartType::LatencyByModel, ProcessedChartData::Boxes { labels, .. }) => {
+                Some(DrillDown::Model {
+                    model: model_of(labels.get(index?)?)?,
+                    range: *range,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Produces the export in the given format. Data formats are ready immediately; PNG asks
+    /// for a screenshot, which arrives in a later frame and is cropped to the chart.
+    fn start_export(&mut self, ui: &Ui, format: ExportFormat, chart_data: &ProcessedChartData) {
+        let chart = self.selected_chart.name();
+        let file_name = format!("{}.{}", file_stem(chart), format.extension());
+        let time_format = self.line_mode().time_format(self.settings.bucket);
+        let rows = || chart_data.rows(time_format);
+        let bytes = match format {
+            ExportFormat::Csv => to_csv(&rows()),
+            ExportFormat::Json => to_json(chart, &rows()),
+            ExportFormat::Svg => {
+                let format_y = if self.is_latency_chart() {
+                    |y: f64| format_seconds(y.max(0.0))
+                } else {
+                    format_number
+                };
+                Ok(
+                    to_svg(chart, chart_data, time_format, self.heatmap_year, format_y)
+                        .into_bytes(),
+                )
+            }
+            ExportFormat::Png => {
+                if let Some(rect) = self.chart_rect {
+                    let request = ChartScreenshot {
+                        id: ui.id(),
+                        rect,
+                        file_name,
+                    };
+                    ui.ctx()
+                        .send_viewport_cmd(egui::ViewportCommand::Screenshot(egui::UserData::new(
+                            request,
+                        )));
+                }
+                return;
+            }
+        };
+        match bytes {
+            Ok(bytes) => self.export = Some(ExportFile { file_name, bytes }),
+            Err(e) => warn!("Failed to export {}: {:#}", file_name, e),
+        }
+    }
+
+    /// Crops screenshots this chart asked for and queues them as PNG exports.
+    fn receive_screenshots(&mut self, ui: &Ui) {
+        let screenshots: Vec<(ChartScreenshot, Arc<egui::ColorImage>)> = ui.ctx().input(|i| {
+            i.raw
+                .events
+                .iter()
+                .filter_map(|event| match event {
+                    egui::Event::Screenshot {
+                        user_data, image, ..
+                    } => {
+                        let request = user_data.data.as_ref()?.downcast_ref::<ChartScreenshot>()?;
+                        (request.id == ui.id()).then(|| (request.clone(), image.clone()))
+                    }
+                    _ => None,
+                })
+                .collect()
+        });
+        for (request, image) in screenshots {
+            let region = image.region(&request.rect, Some(ui.ctx().pixels_per_point()));
+            match to_png(&region) {
+                Ok(bytes) => {
+                    self.export = Some(ExportFile {
+                        file_name: request.file_name,
+                        bytes,
+                    })
+                }
+                Err(e) => warn!("Failed to export {}: {:#}", request.file_name, e),
+            }
+        }
+    }
+
+    /// Returns the modes the selected chart can be displayed in; empty if it isn't a line chart.
+    fn line_modes(&self) -> &'static [LineMode] {
+        match self.selected_chart {
+            ChartType::Timeline | ChartType::ModelsOverTime => LineMode::for_counts(),
+            ChartType::LatencyOverTime => LineMode::for_averages(),
+            _ => &[],
+        }
+    }
+
+    /// Returns the selected line mode if the chart supports it, otherwise the plain values.
+    fn line_mode(&self) -> LineMode {
+        if self.line_modes().contains(&self.line_mode) {
+            self.line_mode
+        } else {
+            LineMode::PerBucket
+        }
+    }
+
+    fn is_latency_chart(&self) -> bool {
+        matches!(
+            self.selected_chart,
+            ChartType::LatencyByModel | ChartType::LatencyOverTime
+        )
+    }
+
+    /// Returns a finished export, if any, so the caller can ask where to save it.
+    pub fn take_export(&mut self) -> Option<ExportFile> {
+        self.export.take()
+    }
+}
+
+impl ChartState {
+    /// Draws the date range pickers and, for time series charts, the bucket size selector.
+    fn draw_settings(&mut self, ui: &mut Ui, messages: &[&T3Message]) {
+        let Some(span) = DateRange::of_messages(messages, self.settings.timezone) else {
+            return;
+        };
+        let mut range = self.settings.range.unwrap_or(span);
+
+        ui.horizontal(|ui| {
+            ui.label("From:");
+            ui.add(
+                egui_extras::DatePickerButton::new(&mut range.start).id_salt("chart_range_start"),
+            );
+            ui.label("To:");
+            ui.add(egui_extras::DatePickerButton::new(&mut range.end).id_salt("chart_range_end"));
+            if self.settings.range.is_some() && ui.button("Whole export").clicked() {
+                self.settings.range = None;
+                return;
+            }
+            if range != self.settings.range.unwrap_or(span) {
+                if range.start > range.end {
+                    std::mem::swap(&mut range.start, &mut range.end);
+                }
+                self.settings.range = Some(range);
+            }
+
+            if matches!(
+                self.selected_chart,
+                ChartType::Timeline | ChartType::ModelsOverTime | ChartType::LatencyOverTime
+            ) {
+                ui.separator();
+                ui.label("Bucket:");
+                egui::ComboBox::from_id_salt("chart_bucket")
+                    .selected_text(self.settings.bucket.name())
+                    .show_ui(ui, |ui| {
+                        for bucket in TimeBucket::all() {
+                            ui.selectable_value(&mut self.settings.bucket, *bucket, bucket.name());
+                        }
+                    });
+            }
+
+            let modes = self.line_modes();
+            if !modes.is_empty() {
+                ui.label("Show:");
+                egui::ComboBox::from_id_salt("chart_line_mode")
+                    .selected_text(self.line_mode().name())
+                    .show_ui(ui, |ui| {
+                        for mode in modes {
+                            ui.selectable_value(&mut self.line_mode, *mode, mode.name());
+                        }
+                    });
+            }
+        });
+    }
+
+    /// Draws a GitHub-style calendar of one year: a column per week, a row per weekday,
+    /// coloured by the number of messages that day. Clicking a day returns it as a drill-down.
+    fn draw_calendar(
+        &mut self,
+        ui: &mut Ui,
+        counts: &BTreeMap<NaiveDate, usize>,
+    ) -> Option<DrillDown> {
+        const CELL: f32 = 12.0;
+        const GAP: f32 = 2.0;
+        const LABEL_WIDTH: f32 = 30.0;
+        const HEADER_HEIGHT: f32 = 16.0;
+
+        let mut years: Vec<i32> = counts.keys().map(|day| day.year()).collect();
+        years.dedup();
+        let &latest_year = years.last()?;
+        let year = match self.heatmap_year {
+            Some(year) if years.contains(&year) => year,
+            _ => latest_year,
+        };
+
+        ui.horizontal(|ui| {
+            ui.label("Year:");
+            egui::ComboBox::from_id_salt("calendar_heatmap_year")
+                .selected_text(year.to_string())
+                .show_ui(ui, |ui| {
+                    for candidate in years.iter().rev() {
+                        ui.selectable_value(
+                            &mut self.heatmap_year,
+                            Some(*candidate),
+                            candidate.to_string(),
+                        );
+                    }
+                });
+            let total: usize = counts
+                .iter()
+                .filter(|(day, _)| day.year() == year)
+                .map(|(_, count)| count)
+                .sum();
+            ui.label(format!("{} messages", total));
+        });
+
+        let first_day = NaiveDate::from_ymd_opt(year, 1, 1)?;
+        let last_day = NaiveDate::from_ymd_opt(year, 12, 31)?;
+        // Columns start on the Monday on or before January 1st
+        let grid_start =
+            first_day - Duration::days(first_day.weekday().num_days_from_monday() as i64);
+        let weeks = ((last_day - grid_start).num_days() / 7 + 1) as usize;
+        let max_count = counts
+            .iter()
+            .filter(|(day, _)| day.year() == year)
+            .map(|(_, count)| *count)
+            .max()
+            .unwrap_or(0);
+
+        let size = egui::vec2(
+            LABEL_WIDTH + weeks as f32 * (CELL + GAP),
+            HEADER_HEIGHT + 7.0 * (CELL + GAP),
+        );
+        let (response, painter) = ui.allocate_painter(size, egui::Sense::click());
+        self.chart_rect = Some(response.rect);
+        let origin = response.rect.min + egui::vec2(LABEL_WIDTH, HEADER_HEIGHT);
+        let text_color = ui.visuals().text_color();
+        let font = egui::FontId::proportional(10.0);
+
+        for (row, label) in [(0, "Mon"), (2, "Wed"), (4, "Fri")] {
+            painter.text(
+                egui::pos2(
+                    response.rect.min.x,
+                    origin.y + row as f32 * (CELL + GAP) + CELL / 2.0,
+                ),
+                egui::Align2::LEFT_CENTER,
+                label,
+                font.clone(),
+                text_color,
+            );
+        }
+
+        let dark_mode = ui.visuals().dark_mode;
+        let pointer = response.hover_pos();
+        let mut hovered = None;
+        let mut day = first_day;
+        while day <= last_day {
+            let offset = (day - grid_start).num_days();
+            let week = (offset / 7) as f32;
+            let weekday = (offset % 7) as f32;
+            let cell = egui::Rect::from_min_size(
+                origin + egui::vec2(week * (CELL + GAP), weekday * (CELL + GAP)),
+                egui::vec2(CELL, CELL),
+            );
+            let count = *counts.get(&day).unwrap_or(&0);
+            painter.rect_filled(cell, 2.0, heatmap_color(count, max_count, dark_mode));
+
+            if day.day() == 1 {
+                painter.text(
+                    egui::pos2(cell.min.x, response.rect.min.y),
+                    egui::Align2::LEFT_TOP,
+                    day.format("%b").to_string(),
+                    font.clone(),
+                    text_color,
+                );
+            }
+            if pointer.is_some_and(|pointer| cell.expand(GAP / 2.0).contains(pointer)) {
+                painter.rect_stroke(
+                    cell,
+                    2.0,
+                    egui::Stroke::new(1.0, text_color),
+                    egui::StrokeKind::Outside,
+                );
+                hovered = Some((day, count));
+            }
+            day += Duration::days(1);
+        }
+
+        let (day, count) = hovered?;
+        let response = response.on_hover_text(format!(
+            "{}: {} message{}",
+            day.format("%a %Y-%m-%d"),
+            count,
+            if count == 1 { "" } else { "s" }
+        ));
+        if response.clicked() {
+            info!("Calendar day clicked: {}", day);
+            return Some(DrillDown::Day(day));
+        }
+        None
+    }
+}
+
+/// Returns the heatmap colour for a day, in five steps like GitHub's contribution graph.
+pub(crate) fn heatmap_color(count: usize, max_count: usize, dark_mode: bool) -> egui::Color32 {
+    let levels = if dark_mode {
+        [
+            egui::Color32::from_rgb(0x16, 0x1b, 0x22),
+            egui::Color32::from_rgb(0x0e, 0x44, 0x29),
+            egui::Color32::from_rgb(0x00, 0x6d, 0x32),
+            egui::Color32::from_rgb(0x26, 0xa6, 0x41),
+            egui::Color32::from_rgb(0x39, 0xd3, 0x53),
+        ]
+    } else {
+        [
+            egui::Color32::from_rgb(0xeb, 0xed, 0xf0),
+            egui::Color32::from_rgb(0x9b, 0xe9, 0xa8),
+            egui::Color32::from_rgb(0x40, 0xc4, 0x63),
+            egui::Color32::from_rgb(0x30, 0xa1, 0x4e),
+            egui::Color32::from_rgb(0x21, 0x6e, 0x39),
+        ]
+    };
+    if count == 0 || max_count == 0 {
+        return levels[0];
+    }
+    let level = 1 + (count - 1) * 4 / max_count;
+    levels[level.min(4)]
+}
+
+/// Returns the name of the donut slice under the pointer, in the layout used by `draw_donut`.
+fn slice_at(slices: &[(String, f64)], pointer: PlotPoint) -> Option<&str> {
+    let radius = pointer.x.hypot(pointer.y);
+    if !(0.5..=1.0).contains(&radius) {
+        return None;
+    }
+    // Slices start at twelve o'clock and go clockwise
+    let turn = (std::f64::consts::FRAC_PI_2 - pointer.y.atan2(pointer.x))
+        .rem_euclid(std::f64::consts::TAU)
+        / std::f64::consts::TAU;
+    let total: f64 = slices.iter().map(|(_, value)| value).sum();
+    let mut start = 0.0;
+    for (name, value) in slices {
+        start += value / total;
+        if turn < start {
+            return Some(name);
+        }
+    }
+    None
+}
+
+/// Draws a donut chart centred on the origin with one legend entry per slice.
+/// Each slice is split into thin segments because `Polygon` only fills convex shapes.
+fn draw_donut(plot_ui: &mut egui_plot::PlotUi, slices: &[(String, f64)]) {
+    const OUTER_RADIUS: f64 = 1.0;
+    const INNER_RADIUS: f64 = 0.5;
+    const SEGMENTS_PER_TURN: f64 = 256.0;
+
+    let total: f64 = slices.iter().map(|(_, value)| value).sum();
+    if total <= 0.0 {
+        return;
+    }
+
+    let point = |radius: f64, angle: f64| [radius * angle.cos(), radius * angle.sin()];
+    // Start at twelve o'clock and go clockwise
+    let mut start = std::f64::consts::FRAC_PI_2;
+    for (index, (name, value)) in slices.iter().enumerate() {
+        let sweep = value / total * std::f64::consts::TAU;
+        let segments = ((sweep / std::f64::consts::TAU) * SEGMENTS_PER_TURN)
+            .ceil()
+            .max(1.0);
+        let step = sweep / segments;
+        let color = series_color(index);
+        let label = format!("{} ({:.1}%)", name, value / total * 100.0);
+        for segment in 0..segments as usize {
+            let a = start - step * segment as f64;
+            let b = a - step;
+            plot_ui.polygon(
+                Polygon::new(
+                    &label,
+                    vec![
+                        point(INNER_RADIUS, a),
+                        point(OUTER_RADIUS, a),
+                        point(OUTER_RADIUS, b),
+                        point(INNER_RADIUS, b),
+                    ],
+                )
+                .fill_color(color)
+                .stroke(egui::Stroke::new(0.0, color))
+                .allow_hover(false),
+            );
+        }
+        start -= sweep;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ChartSettings;
+    use super::ChartState;
+    use super::ChartType;
+    use super::ProcessedChartData;
+    use crate::filter::DrillDown;
+    use crate::t3_json::T3Message;
+    use crate::t3_json::fixture;
+    use crate::time_bucket::DateRange;
+    use chrono::NaiveDate;
+    use chrono::Weekday;
+    use egui_plot::Bar;
+    use egui_plot::PlotPoint;
+
+    #[test]
+    fn clicks_between_bars_miss() {
+        let state = ChartState::new();
+        assert_eq!(state.selected_chart, ChartType::DayOfWeek);
+        let data = ProcessedChartData::Bars {
+            points: (0..7).map(|day| Bar::new(day as f64, 1.0)).collect(),
+            labels: vec![],
+        };
+        let day = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let range = DateRange {
+            start: day,
+            end: day,
+        };
+        let click = |x: f64| state.drill_down_at(&data, PlotPoint::new(x, 0.5), &range);
+        assert_eq!(
+            click(1.2),
+            Some(DrillDown::Weekday {
+                weekday: Weekday::Tue,
+                range,
+            })
+        );
+        assert_eq!(click(1.5), None);
+        assert_eq!(click(-0.4), None);
+    }
+
+    #[test]
+    fn latency_boxes_count_every_prompt() {
+        // Two prompts in a row share the reply, so they are 5 and 4 seconds
+        let messages = [
+            fixture::message("a", "t1").build(),
+            fixture::message("b", "t1")
+                .created_at(fixture::START + 1_000)
+                .build(),
+            fixture::message("c", "t1")
+                .role("assistant")
+                .created_at(fixture::START + 5_000)
+                .build(),
+        ];
+        let messages: Vec<&T3Message> = messages.iter().collect();
+        let ProcessedChartData::Boxes { points, labels } =
+            ChartSettings::default().process_latency_by_model(&messages)
+        else {
+            panic!("expected box plots");
+        };
+        assert_eq!(labels, vec!["m1"]);
+        assert_eq!(points[0].spread.lower_whisker, 4.0);
+        assert_eq!(points[0].spread.upper_whisker, 5.0);
     }
 }
@@ -0,0 +1,190 @@
+// src/cli.rs
+
+use clap::Args;
+use clap::Parser;
+use clap::Subcommand;
+use eyre::Result;
+use std::path::PathBuf;
+
+use crate::cost::CostEstimate;
+use crate::cost::CostReport;
+use crate::cost::PriceTable;
+use crate::cost::TokenizerKind;
+use crate::cost::format_cost;
+use crate::filter::ThreadFilter;
+use crate::sqlite;
+use crate::t3_json::T3Json;
+use crate::tabular::TableFormat;
+use crate::tabular::export_tables;
+use crate::timezone::DisplayTimezone;
+
+/// Number of threads listed by `stats`.
+const TOP_THREAD_COUNT: usize = 10;
+
+#[derive(Parser)]
+#[command(version, about = "View and analyse t3.chat exports")]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Export files to open in the viewer
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Print estimated token usage and cost without opening the viewer
+    Stats(StatsArgs),
+    /// Write exports into a SQLite database with `threads`, `messages` and `messages_fts` tables
+    Sqlite(SqliteArgs),
+    /// Write threads and messages as CSV or Parquet tables for notebooks
+    Tables(TablesArgs),
+}
+
+#[derive(Args)]
+pub struct StatsArgs {
+    /// JSON price table keyed by model id, as saved from the viewer
+    #[arg(long)]
+    pub prices: Option<PathBuf>,
+
+    /// Heuristic used to estimate token counts
+    #[arg(long, value_enum, default_value_t)]
+    pub tokenizer: TokenizerKind,
+
+    /// Timezone used to group messages by month: `local` or an IANA name such as `UTC`
+    #[arg(long, default_value = "local")]
+    pub timezone: DisplayTimezone,
+
+    /// Export files to read; their threads and messages are combined
+    #[arg(required = true)]
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct SqliteArgs {
+    /// Database file to create; an existing file is replaced
+    #[arg(short, long)]
+    pub output: PathBuf,
+
+    /// Export files to read; their threads and messages are combined
+    #[arg(required = true)]
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct TablesArgs {
+    /// Format of the `threads` and `messages` files
+    #[arg(long, value_enum, default_value_t)]
+    pub format: TableFormat,
+
+    /// Directory to write the files into; it is created if needed
+    #[arg(short, long)]
+    pub output_dir: PathBuf,
+
+    /// Export files to read; their threads and messages are combined
+    #[arg(required = true)]
+    pub files: Vec<PathBuf>,
+}
+
+impl Command {
+    pub fn run(self) -> Result<()> {
+        match self {
+            Command::Stats(args) => run_stats(args),
+            Command::Sqlite(args) => run_sqlite(args),
+            Command::Tables(args) => run_tables(args),
+        }
+    }
+}
+
+/// Reads the files and concatenates their threads and messages.
+fn read_combined(paths: &[PathBuf]) -> Result<T3Json> {
+    let mut combined = T3Json {
+        threads: vec![],
+        messages: vec![],
+    };
+    for path in paths {
+        let t3_json = T3Json::read(path)?;
+        combined.threads.extend(t3_json.threads);
+        combined.messages.extend(t3_json.messages);
+    }
+    Ok(combined)
+}
+
+fn run_sqlite(args: SqliteArgs) -> Result<()> {
+    let combined = read_combined(&args.files)?;
+    sqlite::write(&combined, &args.output)?;
+    println!(
+        "Wrote {} threads and {} messages to {}",
+        combined.threads.len(),
+        combined.messages.len(),
+        args.output.display()
+    );
+    Ok(())
+}
+
+fn run_tables(args: TablesArgs) -> Result<()> {
+    let combined = read_combined(&args.files)?;
+    let view = ThreadFilter::default().apply(&combined);
+    std::fs::create_dir_all(&args.output_dir)?;
+    for file in export_tables(&view, args.format)? {
+        let path = args.output_dir.join(&file.file_name);
+        std::fs::write(&path, &file.bytes)?;
+        println!("Wrote {}", path.display());
+    }
+    Ok(())
+}
+
+fn run_stats(args: StatsArgs) -> Result<()> {
+    let prices = match &args.prices {
+        Some(path) => PriceTable::load(path)?,
+        None => PriceTable::default(),
+    };
+    let combined = read_combined(&args.files)?;
+
+    let view = ThreadFilter::default().apply(&combined);
+    let report = CostReport::new(
+        &view,
+        &prices,
+        args.tokenizer.tokenizer().as_ref(),
+        args.timezone,
+    );
+
+    println!(
+        "{} threads, {} messages, tokens estimated with {}",
+        view.threads.len(),
+        view.messages.len(),
+        args.tokenizer.name()
+    );
+    if prices.models.is_empty() {
+        println!("No price table given, costs are zero. Pass --prices to estimate them.");
+    }
+    println!();
+    print_row("Total", &report.total);
+
+    println!("\nPer model");
+    for (model, estimate) in &report.by_model {
+        print_row(model, estimate);
+    }
+
+    println!("\nPer month ({})", args.timezone.name());
+    for (month, estimate) in &report.by_month {
+        print_row(&month.format("%Y-%m").to_string(), estimate);
+    }
+
+    println!("\nMost expensive threads");
+    for (id, title, estimate) in report.by_thread.iter().take(TOP_THREAD_COUNT) {
+        print_row(&format!("{} ({})", title, id), estimate);
+    }
+    Ok(())
+}
+
+fn print_row(name: &str, estimate: &CostEstimate) {
+    println!(
+        "  {:<40} {:>10} in {:>10} out {:>12}",
+        name,
+        estimate.input_tokens,
+        estimate.output_tokens,
+        format_cost(estimate.cost)
+    );
+}
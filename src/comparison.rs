@@ -0,0 +1,267 @@
+// src/comparison.rs
+
+use chrono::DateTime;
+use chrono::Utc;
+use eframe::egui;
+use egui_plot::Legend;
+use egui_plot::Line;
+use egui_plot::Plot;
+use egui_plot::PlotPoint;
+use egui_plot::PlotPoints;
+use std::collections::BTreeSet;
+
+use crate::charts::Series;
+use crate::charts::series_color;
+use crate::filter::ThreadFilter;
+use crate::line_mode::LineMode;
+use crate::t3_json::T3Json;
+use crate::t3_json::T3Message;
+use crate::time_bucket::DateRange;
+use crate::time_bucket::TimeBucket;
+use crate::time_bucket::bucket_counts;
+use crate::timezone::DisplayTimezone;
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// A loaded file taking part in the comparison, with the filter of its own window applied.
+pub struct ComparedFile<'a> {
+    pub name: String,
+    pub t3_json: &'a T3Json,
+    /// The effective filter, see `ThreadFilter::effective`.
+    pub filter: ThreadFilter,
+}
+
+/// Everything the comparison series depend on, so they are only recomputed when it changes.
+#[derive(Debug, Clone, PartialEq)]
+struct ComparisonKey {
+    files: Vec<(String, ThreadFilter)>,
+    bucket: TimeBucket,
+    timezone: DisplayTimezone,
+    align_starts: bool,
+}
+
+/// State for the window comparing message volume across loaded files.
+pub struct ComparisonState {
+    pub bucket: TimeBucket,
+    pub line_mode: LineMode,
+    /// Plots every file from its own first message instead of on a shared calendar,
+    /// e.g. to compare the first weeks of two people's usage.
+    pub align_starts: bool,
+    /// Names of files left out of the comparison.
+    pub hidden: BTreeSet<String>,
+    series: Option<(ComparisonKey, Vec<Series<PlotPoint>>)>,
+}
+
+impl Default for ComparisonState {
+    fn default() -> Self {
+        Self {
+            bucket: TimeBucket::Week,
+            line_mode: LineMode::default(),
+            align_starts: false,
+            hidden: BTreeSet::new(),
+            series: None,
+        }
+    }
+}
+
+impl ComparisonState {
+    /// Counts messages per bucket for every file, one series per file.
+    /// Without alignment all files share the buckets of their combined date range.
+    fn compute(
+        &self,
+        files: &[&ComparedFile],
+        timezone: DisplayTimezone,
+    ) -> Vec<Series<PlotPoint>> {
+        let views: Vec<(&str, Vec<&T3Message>)> = files
+            .iter()
+            .map(|file| (file.name.as_str(), file.filter.apply(file.t3_json).messages))
+            .collect();
+        let all: Vec<&T3Message> = views.iter().flat_map(|(_, m)| m).copied().collect();
+        let Some(shared_range) = DateRange::of_messages(&all, timezone) else {
+            return vec![];
+        };
+
+        views
+            .iter()
+            .filter_map(|(name, messages)| {
+                let range = if self.align_starts {
+                    DateRange::of_messages(messages, timezone)?
+                } else {
+                    shared_range
+                };
+                let counts = bucket_counts(messages, &range, self.bucket, timezone);
+                let origin = match counts.first() {
+                    Some((start, _)) if self.align_starts => start.and_utc().timestamp() as f64,
+                    _ => 0.0,
+                };
+                let values = counts
+                    .into_iter()
+                    .map(|(start, count)| {
+                        PlotPoint::new(start.and_utc().timestamp() as f64 - origin, count as f64)
+                    })
+                    .collect();
+                Some(Series {
+                    name: name.to_string(),
+                    values,
+                })
+            })
+            .collect()
+    }
+
+    /// Draws the file picker, the settings and the overlaid series.
+    pub fn draw(&mut self, ui: &mut egui::Ui, files: &[ComparedFile], timezone: DisplayTimezone) {
+        if files.len() < 2 {
+            ui.label("Load at least two exports to compare them.");
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Files:");
+            for file in files {
+                let mut shown = !self.hidden.contains(&file.name);
+                if ui.checkbox(&mut shown, &file.name).changed() {
+                    if shown {
+                        self.hidden.remove(&file.name);
+                    } else {
+                        self.hidden.insert(file.name.clone());
+                    }
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Bucket:");
+            egui::ComboBox::from_id_salt("comparison_bucket")
+                .selected_text(self.bucket.name())
+                .show_ui(ui, |ui| {
+                    for bucket in TimeBucket::all() {
+                        ui.selectable_value(&mut self.bucket, *bucket, bucket.name());
+                    }
+                });
+            ui.label("Show:");
+            egui::ComboBox::from_id_salt("comparison_line_mode")
+                .selected_text(self.line_mode().name())
+                .show_ui(ui, |ui| {
+                    for mode in self.line_modes() {
+                        ui.selectable_value(&mut self.line_mode, mode, mode.name());
+                    }
+                });
+            ui.checkbox(&mut self.align_starts, "Align first messages");
+        });
+        ui.label("Each file's own search and facet filters apply.");
+
+        let files: Vec<&ComparedFile> = files
+            .iter()
+            .filter(|file| !self.hidden.contains(&file.name))
+            .collect();
+        let key = ComparisonKey {
+            files: files
+                .iter()
+                .map(|file| (file.name.clone(), file.filter.clone()))
+                .collect(),
+            bucket: self.bucket,
+            timezone,
+            align_starts: self.align_starts,
+        };
+        if self
+            .series
+            .as_ref()
+            .is_none_or(|(cached, _)| *cached != key)
+        {
+            self.series = Some((key, self.compute(&files, timezone)));
+        }
+        let Some((_, series)) = &self.series else {
+            return;
+        };
+        let series = self.line_mode().apply(series);
+
+        let mut plot = Plot::new("comparison_plot")
+            .height(300.0)
+            .legend(Legend::default())
+            .y_axis_label("Messages");
+        plot = if self.align_starts {
+            plot.x_axis_formatter(|x, _range| format!("Day {:.0}", x.value / SECONDS_PER_DAY))
+        } else {
+            let format = self.line_mode().time_format(self.bucket);
+            plot.x_axis_formatter(move |x, _range| {
+                DateTime::<Utc>::from_timestamp(x.value.round() as i64, 0)
+                    .map(|date_time| date_time.format(format).to_string())
+                    .unwrap_or_default()
+            })
+        };
+        plot.show(ui, |plot_ui| {
+            for (index, line) in series.iter().enumerate() {
+                plot_ui.line(
+                    Line::new(&line.name, PlotPoints::Borrowed(&line.values))
+                        .color(series_color(index)),
+                );
+            }
+        });
+    }
+
+    /// Year-over-year overlays need calendar dates, so they are unavailable when aligned.
+    fn line_modes(&self) -> Vec<LineMode> {
+        LineMode::for_counts()
+            .iter()
+            .copied()
+            .filter(|mode| !(self.align_starts && *mode == LineMode::YearOverYear))
+            .collect()
+    }
+
+    fn line_mode(&self) -> LineMode {
+        if self.line_modes().contains(&self.line_mode) {
+            self.line_mode
+        } else {
+            LineMode::PerBucket
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ComparedFile;
+    use super::ComparisonState;
+    use crate::filter::ThreadFilter;
+    use crate::t3_json::T3Json;
+    use crate::t3_json::fixture;
+    use crate::time_bucket::TimeBucket;
+    use crate::timezone::DisplayTimezone;
+
+    fn export(created_at: &[i64]) -> T3Json {
+        fixture::export(
+            [],
+            created_at.iter().enumerate().map(|(index, created_at)| {
+                fixture::message(&index.to_string(), "t1").created_at(fixture::START + created_at)
+            }),
+        )
+    }
+
+    #[test]
+    fn aligned_files_start_at_zero() {
+        const DAY: i64 = 86_400_000;
+        let early = export(&[0, DAY]);
+        let late = export(&[100 * DAY, 100 * DAY, 102 * DAY]);
+        let filter = ThreadFilter::default();
+        let files = [
+            ComparedFile {
+                name: "early".to_string(),
+                t3_json: &early,
+                filter: filter.clone(),
+            },
+            ComparedFile {
+                name: "late".to_string(),
+                t3_json: &late,
+                filter,
+            },
+        ];
+        let state = ComparisonState {
+            bucket: TimeBucket::Day,
+            align_starts: true,
+            ..Default::default()
+        };
+        let files: Vec<&ComparedFile> = files.iter().collect();
+        let series = state.compute(&files, DisplayTimezone::Named(chrono_tz::UTC));
+        assert_eq!(series.len(), 2);
+        assert_eq!(series[1].values[0].x, 0.0);
+        assert_eq!(series[1].values[0].y, 2.0);
+        assert_eq!(series[1].values.len(), 3);
+    }
+}
@@ -0,0 +1,435 @@
+// src/cost.rs
+
+use chrono::NaiveDate;
+use eframe::egui;
+use egui_extras::Column;
+use egui_extras::TableBuilder;
+use eyre::Context;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::filter::DrillDown;
+use crate::filter::FilteredView;
+use crate::filter::ThreadFilter;
+use crate::t3_json::T3MessageRole;
+use crate::time_bucket::TimeBucket;
+use crate::timezone::DisplayTimezone;
+
+/// Number of threads listed in the most expensive threads table.
+const TOP_THREAD_COUNT: usize = 20;
+
+/// Estimates how many tokens a model would see for a piece of text.
+pub trait Tokenizer {
+    fn estimate(&self, text: &str) -> usize;
+}
+
+/// Assumes a fixed number of characters per token.
+pub struct CharsPerToken(pub f64);
+
+impl Default for CharsPerToken {
+    /// Roughly four characters per token for English text.
+    fn default() -> Self {
+        Self(4.0)
+    }
+}
+
+impl Tokenizer for CharsPerToken {
+    fn estimate(&self, text: &str) -> usize {
+        (text.chars().count() as f64 / self.0).ceil() as usize
+    }
+}
+
+/// Assumes a fixed number of tokens per whitespace-separated word.
+pub struct TokensPerWord(pub f64);
+
+impl Default for TokensPerWord {
+    /// Roughly three words per four tokens for English text.
+    fn default() -> Self {
+        Self(4.0 / 3.0)
+    }
+}
+
+impl Tokenizer for TokensPerWord {
+    fn estimate(&self, text: &str) -> usize {
+        (text.split_whitespace().count() as f64 * self.0).ceil() as usize
+    }
+}
+
+/// The built-in tokenizer heuristics that can be picked in the GUI and on the command line.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash, clap::ValueEnum)]
+pub enum TokenizerKind {
+    /// Four characters per token
+    #[default]
+    Chars,
+    /// Four tokens per three words
+    Words,
+}
+
+impl TokenizerKind {
+    /// Returns a human-readable name for the heuristic.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenizerKind::Chars => "4 chars per token",
+            TokenizerKind::Words => "4 tokens per 3 words",
+        }
+    }
+
+    /// Returns all available heuristics.
+    pub fn all() -> &'static [TokenizerKind] {
+        &[TokenizerKind::Chars, TokenizerKind::Words]
+    }
+
+    pub fn tokenizer(&self) -> Box<dyn Tokenizer> {
+        match self {
+            TokenizerKind::Chars => Box::new(CharsPerToken::default()),
+            TokenizerKind::Words => Box::new(TokensPerWord::default()),
+        }
+    }
+}
+
+/// Price of a model in dollars per million tokens.
+#[derive(Debug, Default, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+/// Prices keyed by model id, as stored in a JSON file such as
+/// `{"gpt-4o": {"input_per_million": 2.5, "output_per_million": 10.0}}`.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PriceTable {
+    pub models: BTreeMap<String, ModelPrice>,
+}
+
+impl PriceTable {
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let bytes = std::fs::read(path)
+            .wrap_err_with(|| format!("Failed to read price table {}", path.display()))?;
+        serde_json::from_slice(&bytes)
+            .wrap_err_with(|| format!("Failed to parse price table {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> eyre::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)
+            .wrap_err_with(|| format!("Failed to write price table {}", path.display()))
+    }
+
+    /// Returns the price for a model, or zero if it is not in the table.
+    pub fn price(&self, model: &str) -> ModelPrice {
+        self.models.get(model).copied().unwrap_or_default()
+    }
+}
+
+/// Estimated token counts and cost for a group of messages.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct CostEstimate {
+    pub input_tokens: usize,
+    pub output_tokens: usize,
+    pub cost: f64,
+}
+
+impl std::ops::AddAssign for CostEstimate {
+    fn add_assign(&mut self, other: Self) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cost += other.cost;
+    }
+}
+
+/// Estimated costs of a set of messages, broken down several ways.
+#[derive(Debug, Default, Clone)]
+pub struct CostReport {
+    pub total: CostEstimate,
+    pub by_model: BTreeMap<String, CostEstimate>,
+    /// Keyed by the first day of the month, in the report's timezone.
+    pub by_month: BTreeMap<NaiveDate, CostEstimate>,
+    /// `(thread id, title, estimate)`, most expensive first.
+    pub by_thread: Vec<(String, String, CostEstimate)>,
+}
+
+impl CostReport {
+    /// Estimates costs for the messages in the view.
+    ///
+    /// User messages are priced as input and assistant messages as output, both at the price of
+    /// the message's model. Context re-sent with every request is not counted, so this is a lower
+    /// bound for long threads.
+    pub fn new(
+        view: &FilteredView,
+        prices: &PriceTable,
+        tokenizer: &dyn Tokenizer,
+        timezone: DisplayTimezone,
+    ) -> Self {
+        let titles: HashMap<&str, &str> = view
+            .threads
+            .iter()
+            .map(|t| (t.id.as_str(), t.title.as_str()))
+            .collect();
+        let mut report = CostReport::default();
+        let mut by_thread: HashMap<&str, CostEstimate> = HashMap::new();
+        for message in &view.messages {
+            let tokens = tokenizer.estimate(&message.content);
+            let price = prices.price(&message.model);
+            let estimate = match message.role {
+                T3MessageRole::User => CostEstimate {
+                    input_tokens: tokens,
+                    output_tokens: 0,
+                    cost: tokens as f64 * price.input_per_million / 1_000_000.0,
+                },
+                T3MessageRole::Assistant => CostEstimate {
+                    input_tokens: 0,
+                    output_tokens: tokens,
+                    cost: tokens as f64 * price.output_per_million / 1_000_000.0,
+                },
+            };
+            let month = TimeBucket::Month
+                .floor(timezone.naive(*message.created_at))
+                .date();
+
+            report.total += estimate;
+            *report.by_model.entry(message.model.clone()).or_default() += estimate;
+            *report.by_month.entry(month).or_default() += estimate;
+            *by_thread.entry(message.thread_id.as_str()).or_default() += estimate;
+        }
+
+        report.by_thread = by_thread
+            .into_iter()
+            .map(|(id, estimate)| {
+                let title = titles.get(id).copied().unwrap_or_default().to_string();
+                (id.to_string(), title, estimate)
+            })
+            .collect();
+        report.by_thread.sort_by(|a, b| {
+            b.2.cost
+                .total_cmp(&a.2.cost)
+                .then(b.2.output_tokens.cmp(&a.2.output_tokens))
+        });
+        report
+    }
+}
+
+/// Formats a dollar amount, with more precision for small amounts.
+pub fn format_cost(cost: f64) -> String {
+    if cost != 0.0 && cost.abs() < 0.01 {
+        format!("${:.4}", cost)
+    } else {
+        format!("${:.2}", cost)
+    }
+}
+
+/// State for the cost estimate view of a file.
+#[derive(Default)]
+pub struct CostState {
+    report: Option<(
+        (ThreadFilter, PriceTable, TokenizerKind, DisplayTimezone),
+        CostReport,
+    )>,
+}
+
+impl CostState {
+    /// Draws cost per model, per month and the most expensive threads.
+    /// Clicking a thread returns a drill-down to it.
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        view: &FilteredView,
+        filter: &ThreadFilter,
+        prices: &PriceTable,
+        tokenizer: TokenizerKind,
+        timezone: DisplayTimezone,
+    ) -> Option<DrillDown> {
+        let key = (filter.clone(), prices.clone(), tokenizer, timezone);
+        if self
+            .report
+            .as_ref()
+            .is_none_or(|(cached, _)| *cached != key)
+        {
+            let report = CostReport::new(view, prices, tokenizer.tokenizer().as_ref(), timezone);
+            self.report = Some((key, report));
+        }
+        let Some((_, report)) = &self.report else {
+            return None;
+        };
+
+        ui.label(format!(
+            "Total: {} ({} input tokens, {} output tokens, using {})",
+            format_cost(report.total.cost),
+            report.total.input_tokens,
+            report.total.output_tokens,
+            tokenizer.name(),
+        ));
+        if prices.models.is_empty() {
+            ui.label("The price table is empty. Open it from the top bar to add model prices.");
+        }
+
+        ui.columns(2, |columns| {
+            columns[0].strong("Per model");
+            draw_estimates(
+                &mut columns[0],
+                "cost_by_model",
+                report.by_model.iter().map(|(model, e)| (model.clone(), *e)),
+            );
+            columns[1].strong("Per month");
+            draw_estimates(
+                &mut columns[1],
+                "cost_by_month",
+                report
+                    .by_month
+                    .iter()
+                    .map(|(month, e)| (month.format("%Y-%m").to_string(), *e)),
+            );
+        });
+
+        let mut drill_down = None;
+        ui.strong("Most expensive threads");
+        for (id, title, estimate) in report.by_thread.iter().take(TOP_THREAD_COUNT) {
+            ui.horizontal(|ui| {
+                ui.monospace(format_cost(estimate.cost));
+                if ui.link(title).clicked() {
+                    drill_down = Some(DrillDown::Thread {
+                        id: id.clone(),
+                        title: title.clone(),
+                    });
+                }
+            });
+        }
+        drill_down
+    }
+}
+
+fn draw_estimates(
+    ui: &mut egui::Ui,
+    id_salt: &str,
+    rows: impl Iterator<Item = (String, CostEstimate)>,
+) {
+    let rows: Vec<(String, CostEstimate)> = rows.collect();
+    TableBuilder::new(ui)
+        .id_salt(id_salt)
+        .striped(true)
+        .max_scroll_height(200.0)
+        .column(Column::auto().at_least(100.0).clip(true))
+        .columns(Column::auto(), 3)
+        .header(20.0, |mut header| {
+            for title in ["", "In", "Out", "Cost"] {
+                header.col(|ui| {
+                    ui.strong(title);
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(18.0, rows.len(), |mut row| {
+                let (name, estimate) = &rows[row.index()];
+                row.col(|ui| {
+                    ui.label(name);
+                });
+                row.col(|ui| {
+                    ui.monospace(estimate.input_tokens.to_string());
+                });
+                row.col(|ui| {
+                    ui.monospace(estimate.output_tokens.to_string());
+                });
+                row.col(|ui| {
+                    ui.monospace(format_cost(estimate.cost));
+                });
+            });
+        });
+}
+
+/// Draws an editable price table. `models` are listed even when they have no price yet.
+/// Returns true when a price was changed.
+pub fn draw_price_table<'a>(
+    ui: &mut egui::Ui,
+    prices: &mut PriceTable,
+    models: impl Iterator<Item = &'a str>,
+) -> bool {
+    let mut rows: Vec<String> = prices.models.keys().cloned().collect();
+    rows.extend(models.map(str::to_string));
+    rows.sort();
+    rows.dedup();
+
+    let mut changed = false;
+    egui::Grid::new("price_table").striped(true).show(ui, |ui| {
+        ui.strong("Model");
+        ui.strong("$ / 1M input");
+        ui.strong("$ / 1M output");
+        ui.end_row();
+        for model in rows {
+            let mut price = prices.price(&model);
+            ui.label(&model);
+            let input = ui.add(
+                egui::DragValue::new(&mut price.input_per_million)
+                    .speed(0.01)
+                    .range(0.0..=f64::MAX),
+            );
+            let output = ui.add(
+                egui::DragValue::new(&mut price.output_per_million)
+                    .speed(0.01)
+                    .range(0.0..=f64::MAX),
+            );
+            if input.changed() || output.changed() {
+                prices.models.insert(model, price);
+                changed = true;
+            }
+            ui.end_row();
+        }
+    });
+    changed
+}
+
+#[cfg(test)]
+mod test {
+    use super::CharsPerToken;
+    use super::CostReport;
+    use super::ModelPrice;
+    use super::PriceTable;
+    use super::Tokenizer;
+    use super::TokensPerWord;
+    use crate::filter::ThreadFilter;
+    use crate::t3_json::fixture;
+    use crate::timezone::DisplayTimezone;
+
+    #[test]
+    fn tokenizers_round_up() {
+        assert_eq!(CharsPerToken::default().estimate("hello"), 2);
+        assert_eq!(TokensPerWord::default().estimate("one two three"), 4);
+        assert_eq!(CharsPerToken::default().estimate(""), 0);
+    }
+
+    #[test]
+    fn prices_input_and_output_separately() {
+        let t3_json = fixture::export(
+            [fixture::thread("t1")],
+            [
+                fixture::message("a", "t1").content("abcdabcd"),
+                fixture::message("b", "t1")
+                    .role("assistant")
+                    .content("abcd")
+                    .created_at(fixture::START + 1000),
+            ],
+        );
+        let mut prices = PriceTable::default();
+        prices.models.insert(
+            "m1".to_string(),
+            ModelPrice {
+                input_per_million: 1_000_000.0,
+                output_per_million: 2_000_000.0,
+            },
+        );
+        let view = ThreadFilter::default().apply(&t3_json);
+        let report = CostReport::new(
+            &view,
+            &prices,
+            &CharsPerToken::default(),
+            DisplayTimezone::Named(chrono_tz::UTC),
+        );
+        assert_eq!(report.total.input_tokens, 2);
+        assert_eq!(report.total.output_tokens, 1);
+        assert_eq!(report.total.cost, 4.0);
+        assert_eq!(report.by_thread.len(), 1);
+        assert_eq!(report.by_month.len(), 1);
+    }
+}
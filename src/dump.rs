@@ -0,0 +1,200 @@
+// src/dump.rs
+
+use eyre::Context;
+use eyre::bail;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tracing::info;
+
+use crate::t3_json::T3Json;
+use crate::t3_json::T3Message;
+use crate::t3_json::T3Thread;
+
+/// Reads an export, falling back to the shapes people copy out of devtools when it isn't a
+/// t3.chat export:
+///
+/// - an object with only `threads` or only `messages`, e.g. from copying one `dxdb` table;
+/// - an array of thread and/or message records, e.g. `await dxdb.messages.toArray()`;
+/// - a Dexie export-import file, written by `dexie-export-import`'s `exportDB`.
+pub fn parse(bytes: &[u8]) -> eyre::Result<T3Json> {
+    let error = match serde_json::from_slice::<T3Json>(bytes) {
+        Ok(t3_json) => return Ok(t3_json),
+        Err(e) => e,
+    };
+    // Anything that isn't JSON at all can't be a dump either
+    let value: Value = serde_json::from_slice(bytes).map_err(|_| error)?;
+    from_value(value)
+}
+
+/// Detects the shape of a parsed dump and reads it.
+pub fn from_value(value: Value) -> eyre::Result<T3Json> {
+    match value {
+        Value::Object(mut object) => {
+            if object.get("formatName").and_then(Value::as_str) == Some("dexie") {
+                let tables = object
+                    .remove("data")
+                    .and_then(|mut data| data.get_mut("data").map(Value::take));
+                let Some(Value::Array(tables)) = tables else {
+                    bail!("Dexie export has no table data");
+                };
+                return dexie_tables(tables);
+            }
+            if !object.contains_key("threads") && !object.contains_key("messages") {
+                bail!("JSON object has neither threads nor messages");
+            }
+            let threads = object.remove("threads").unwrap_or_default();
+            let messages = object.remove("messages").unwrap_or_default();
+            Ok(T3Json {
+                threads: rows(threads, "threads")?,
+                messages: rows(messages, "messages")?,
+            })
+        }
+        Value::Array(records) => from_records(records),
+        _ => bail!("Expected a JSON object or array"),
+    }
+}
+
+/// Sorts records into threads and messages. Messages are told apart by their `threadId`.
+fn from_records(records: Vec<Value>) -> eyre::Result<T3Json> {
+    let mut t3_json = T3Json {
+        threads: vec![],
+        messages: vec![],
+    };
+    for (index, record) in records.into_iter().enumerate() {
+        if record.get("threadId").is_some() {
+            t3_json.messages.push(
+                serde_json::from_value(record)
+                    .wrap_err_with(|| format!("Record {} is not a valid message", index))?,
+            );
+        } else if record.get("title").is_some() {
+            t3_json.threads.push(
+                serde_json::from_value(record)
+                    .wrap_err_with(|| format!("Record {} is not a valid thread", index))?,
+            );
+        } else {
+            bail!("Record {} is neither a thread nor a message", index);
+        }
+    }
+    info!(
+        "Read {} threads and {} messages from an array of records",
+        t3_json.threads.len(),
+        t3_json.messages.len()
+    );
+    Ok(t3_json)
+}
+
+/// Reads the `threads` and `messages` tables of a Dexie export, ignoring any others.
+/// Dates arrive as ISO strings tagged in `$types`, which the timestamp parser reads as is.
+fn dexie_tables(tables: Vec<Value>) -> eyre::Result<T3Json> {
+    let mut t3_json = T3Json {
+        threads: vec![],
+        messages: vec![],
+    };
+    for mut table in tables {
+        let rows_value = table.get_mut("rows").map(Value::take).unwrap_or_default();
+        match table.get("tableName").and_then(Value::as_str) {
+            Some("threads") => t3_json
+                .threads
+                .extend(rows::<T3Thread>(rows_value, "threads")?),
+            Some("messages") => t3_json
+                .messages
+                .extend(rows::<T3Message>(rows_value, "messages")?),
+            other => info!("Skipping Dexie table {:?}", other),
+        }
+    }
+    Ok(t3_json)
+}
+
+/// Reads an array of rows, where null or a missing key means no rows.
+fn rows<T: DeserializeOwned>(value: Value, table: &str) -> eyre::Result<Vec<T>> {
+    if value.is_null() {
+        return Ok(vec![]);
+    }
+    serde_json::from_value(value).wrap_err_with(|| format!("Failed to read {}", table))
+}
+
+/// Which half of an export a dump holds, when threads and messages were saved separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpPart {
+    Threads,
+    Messages,
+}
+
+impl DumpPart {
+    /// Returns the part an export holds, or `None` when it has both halves or neither.
+    pub fn of(t3_json: &T3Json) -> Option<DumpPart> {
+        match (t3_json.threads.is_empty(), t3_json.messages.is_empty()) {
+            (false, true) => Some(DumpPart::Threads),
+            (true, false) => Some(DumpPart::Messages),
+            _ => None,
+        }
+    }
+
+    /// Returns the part that completes this one.
+    pub fn other(&self) -> DumpPart {
+        match self {
+            DumpPart::Threads => DumpPart::Messages,
+            DumpPart::Messages => DumpPart::Threads,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DumpPart;
+    use super::parse;
+    use crate::t3_json::fixture;
+    use serde_json::json;
+
+    /// Records as `toArray()` returns them, with dates as ISO strings.
+    fn thread() -> serde_json::Value {
+        fixture::thread("t1")
+            .set("created_at", "2025-01-01T00:00:00.000Z")
+            .set("last_message_at", "2025-01-01T00:00:00.000Z")
+            .json()
+    }
+
+    fn message() -> serde_json::Value {
+        fixture::message("a", "t1")
+            .created_at("2025-01-01T00:00:00.000Z")
+            .json()
+    }
+
+    fn parse_value(value: serde_json::Value) -> eyre::Result<crate::t3_json::T3Json> {
+        parse(&serde_json::to_vec(&value)?)
+    }
+
+    #[test]
+    fn reads_devtools_shapes() -> eyre::Result<()> {
+        let threads_only = parse_value(json!({ "threads": [thread()] }))?;
+        assert_eq!(DumpPart::of(&threads_only), Some(DumpPart::Threads));
+
+        let records = parse_value(json!([message(), thread(), message()]))?;
+        assert_eq!(records.threads.len(), 1);
+        assert_eq!(records.messages.len(), 2);
+        assert_eq!(DumpPart::of(&records), None);
+
+        let mut dexie_message = message();
+        dexie_message["$types"] = json!({ "created_at": "date" });
+        let dexie = parse_value(json!({
+            "formatName": "dexie",
+            "formatVersion": 1,
+            "data": {
+                "databaseName": "t3chat",
+                "databaseVersion": 1,
+                "tables": [],
+                "data": [
+                    { "tableName": "threads", "inbound": true, "rows": [thread()] },
+                    { "tableName": "messages", "inbound": true, "rows": [dexie_message] },
+                    { "tableName": "settings", "inbound": true, "rows": [{ "key": "theme" }] },
+                ],
+            },
+        }))?;
+        assert_eq!(dexie.threads.len(), 1);
+        assert_eq!(dexie.messages.len(), 1);
+
+        assert!(parse_value(json!([{ "key": "theme" }])).is_err());
+        assert!(parse_value(json!({ "settings": [] })).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,436 @@
+// src/filter.rs
+
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use chrono::Datelike;
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use chrono::Timelike;
+use chrono::Weekday;
+use serde::Serialize;
+
+use crate::model_params::ReasoningEffort;
+use crate::model_params::reasoning_effort;
+use crate::model_params::used_search;
+use crate::t3_json::T3Json;
+use crate::t3_json::T3Message;
+use crate::t3_json::T3Thread;
+use crate::t3_timestamp::TimestampForm;
+use crate::t3_timestamp::serialize_as;
+use crate::time_bucket::DateRange;
+use crate::timezone::DisplayTimezone;
+
+/// The search query and facets that narrow down which threads and messages are shown.
+///
+/// Charts are computed over the same filtered view as the thread list, so `effective` is also
+/// used as the key for the chart cache.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ThreadFilter {
+    /// Case-insensitive match against thread titles and message content.
+    pub search_query: String,
+    /// Only keep messages produced by these models. Empty means every model.
+    pub models: BTreeSet<String>,
+    /// Only keep messages sent with these reasoning efforts, `None` being messages sent
+    /// without one. Empty means every effort.
+    pub reasoning_efforts: BTreeSet<Option<ReasoningEffort>>,
+    /// When set, only keep messages sent with web search on (`true`) or off (`false`).
+    pub web_search: Option<bool>,
+    /// Thread ids ticked in the thread list.
+    pub selected_threads: BTreeSet<String>,
+    /// When true, only the ticked threads are kept.
+    pub selected_only: bool,
+}
+
+/// The threads and messages of a `T3Json` that pass a `ThreadFilter`.
+/// Serializes in the shape of a t3.chat export.
+#[derive(Serialize)]
+pub struct FilteredView<'a> {
+    pub threads: Vec<&'a T3Thread>,
+    pub messages: Vec<&'a T3Message>,
+}
+
+impl ThreadFilter {
+    /// Returns true when the filter keeps everything.
+    pub fn is_empty(&self) -> bool {
+        self.search_query.is_empty() && !self.narrows_messages() && !self.selected_only
+    }
+
+    /// Returns the filter without the ticked threads unless `selected_only` is set, as they
+    /// don't change the filtered view otherwise. Caches over the view are keyed on this, so
+    /// ticking a thread doesn't recompute them.
+    pub fn effective(&self) -> ThreadFilter {
+        if self.selected_only {
+            return self.clone();
+        }
+        ThreadFilter {
+            selected_threads: BTreeSet::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Returns true when the message facets leave out some messages.
+    fn narrows_messages(&self) -> bool {
+        !self.models.is_empty() || !self.reasoning_efforts.is_empty() || self.web_search.is_some()
+    }
+
+    /// Returns true when the message passes the model, reasoning effort and web search facets.
+    pub fn keeps_message(&self, message: &T3Message) -> bool {
+        (self.models.is_empty() || self.models.contains(&message.model))
+            && (self.reasoning_efforts.is_empty()
+                || self
+                    .reasoning_efforts
+                    .contains(&reasoning_effort(message).cloned()))
+            && self
+                .web_search
+                .is_none_or(|web_search| used_search(message) == web_search)
+    }
+
+    /// Applies the filter to an export.
+    ///
+    /// A thread is kept when its title or any of its messages match the search query, it has at
+    /// least one message passing the message facets and, if `selected_only` is set, it is ticked.
+    /// The returned messages are those of the kept threads that pass the message facets.
+    pub fn apply<'a>(&self, t3_json: &'a T3Json) -> FilteredView<'a> {
+        if self.is_empty() {
+            return FilteredView {
+                threads: t3_json.threads.iter().collect(),
+                messages: t3_json.messages.iter().collect(),
+            };
+        }
+
+        let query = self.search_query.to_lowercase();
+        let messages_by_thread: HashMap<&str, Vec<&T3Message>> = t3_json
+            .messages
+            .iter()
+            .filter(|m| self.keeps_message(m))
+            .fold(HashMap::new(), |mut map, m| {
+                map.entry(m.thread_id.as_str()).or_default().push(m);
+                map
+            });
+
+        let threads: Vec<&T3Thread> = t3_json
+            .threads
+            .iter()
+            .filter(|thread| !self.selected_only || self.selected_threads.contains(&thread.id))
+            .filter(|thread| {
+                let messages = messages_by_thread.get(thread.id.as_str());
+                if self.narrows_messages() && messages.is_none() {
+                    return false;
+                }
+                query.is_empty()
+                    || thread.title.to_lowercase().contains(&query)
+                    || messages.is_some_and(|msgs| {
+                        msgs.iter()
+                            .any(|m| m.content.to_lowercase().contains(&query))
+                    })
+            })
+            .collect();
+
+        let thread_ids: HashSet<&str> = threads.iter().map(|t| t.id.as_str()).collect();
+        let messages = t3_json
+            .messages
+            .iter()
+            .filter(|m| self.keeps_message(m))
+            .filter(|m| thread_ids.contains(m.thread_id.as_str()))
+            .collect();
+
+        FilteredView { threads, messages }
+    }
+}
+
+/// A narrowing of the thread list picked from a chart, such as a day clicked in the heatmap.
+///
+/// Unlike `ThreadFilter` this only applies to the thread list; charts keep showing the
+/// surrounding data so another bucket can be picked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DrillDown {
+    /// Messages sent on this day.
+    Day(NaiveDate),
+    /// A single thread, picked from a table or outlier list.
+    Thread { id: String, title: String },
+    /// Messages sent on this weekday within the charted range.
+    Weekday { weekday: Weekday, range: DateRange },
+    /// Messages sent in a 30-minute bin of the day (0 is 00:00-00:30) within the charted range.
+    TimeOfDay { bin: u32, range: DateRange },
+    /// Messages in a time bucket, optionally only those of one model.
+    /// `label` is the bucket as shown on the chart's axis.
+    Period {
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        label: String,
+        model: Option<String>,
+    },
+    /// Messages of a model within the charted range.
+    Model { model: String, range: DateRange },
+    /// Messages sent with a reasoning effort, or without one, within the charted range.
+    ReasoningEffort {
+        effort: Option<ReasoningEffort>,
+        range: DateRange,
+    },
+}
+
+impl DrillDown {
+    /// Returns true when the message falls into this bucket, judged in the given timezone.
+    pub fn matches(&self, message: &T3Message, timezone: DisplayTimezone) -> bool {
+        let date_time = timezone.naive(*message.created_at);
+        match self {
+            DrillDown::Day(day) => date_time.date() == *day,
+            DrillDown::Thread { id, .. } => message.thread_id == *id,
+            DrillDown::Weekday { weekday, range } => {
+                range.contains(date_time.date()) && date_time.weekday() == *weekday
+            }
+            DrillDown::TimeOfDay { bin, range } => {
+                range.contains(date_time.date())
+                    && date_time.hour() * 2 + date_time.minute() / 30 == *bin
+            }
+            DrillDown::Period {
+                start, end, model, ..
+            } => {
+                *start <= date_time
+                    && date_time < *end
+                    && model.as_ref().is_none_or(|model| message.model == *model)
+            }
+            DrillDown::Model { model, range } => {
+                range.contains(date_time.date()) && message.model == *model
+            }
+            DrillDown::ReasoningEffort { effort, range } => {
+                range.contains(date_time.date()) && reasoning_effort(message) == effort.as_ref()
+            }
+        }
+    }
+
+    /// Returns a short description for the thread list header.
+    pub fn describe(&self) -> String {
+        match self {
+            DrillDown::Day(day) => format!("threads with messages on {}", day.format("%Y-%m-%d")),
+            DrillDown::Thread { title, .. } => format!("the thread \"{}\"", title),
+            DrillDown::Weekday { weekday, range } => format!(
+                "threads with messages on a {} {}",
+                weekday,
+                describe_range(range)
+            ),
+            DrillDown::TimeOfDay { bin, range } => {
+                let start = bin * 30;
+                let end = (start + 30) % (24 * 60);
+                format!(
+                    "threads with messages between {:02}:{:02} and {:02}:{:02} {}",
+                    start / 60,
+                    start % 60,
+                    end / 60,
+                    end % 60,
+                    describe_range(range)
+                )
+            }
+            DrillDown::Period {
+                label,
+                model: Some(model),
+                ..
+            } => format!("threads with {} messages in {}", model, label),
+            DrillDown::Period {
+                label, model: None, ..
+            } => format!("threads with messages in {}", label),
+            DrillDown::Model { model, range } => {
+                format!("threads with {} messages {}", model, describe_range(range))
+            }
+            DrillDown::ReasoningEffort { effort, range } => format!(
+                "threads with messages with reasoning effort {} {}",
+                ReasoningEffort::label(effort.as_ref()),
+                describe_range(range)
+            ),
+        }
+    }
+}
+
+fn describe_range(range: &DateRange) -> String {
+    format!(
+        "from {} to {}",
+        range.start.format("%Y-%m-%d"),
+        range.end.format("%Y-%m-%d")
+    )
+}
+
+impl<'a> FilteredView<'a> {
+    /// Keeps only the messages matching the drill-down and the threads containing them.
+    pub fn drill_down(self, drill_down: &DrillDown, timezone: DisplayTimezone) -> FilteredView<'a> {
+        let messages: Vec<&T3Message> = self
+            .messages
+            .into_iter()
+            .filter(|m| drill_down.matches(m, timezone))
+            .collect();
+        let thread_ids: HashSet<&str> = messages.iter().map(|m| m.thread_id.as_str()).collect();
+        let threads = self
+            .threads
+            .into_iter()
+            .filter(|t| thread_ids.contains(t.id.as_str()))
+            .collect();
+        FilteredView { threads, messages }
+    }
+
+    /// Returns the view's threads with all of their messages, including those left out by the
+    /// message facets or a drill-down, so the threads are complete when imported into t3.chat.
+    pub fn complete_threads(&self, t3_json: &'a T3Json) -> FilteredView<'a> {
+        let thread_ids: HashSet<&str> = self.threads.iter().map(|t| t.id.as_str()).collect();
+        FilteredView {
+            threads: self.threads.clone(),
+            messages: t3_json
+                .messages
+                .iter()
+                .filter(|m| thread_ids.contains(m.thread_id.as_str()))
+                .collect(),
+        }
+    }
+
+    /// Serializes the view as an export t3.chat can import. Every timestamp is written in
+    /// milliseconds, the form t3.chat itself exports, whatever form it was loaded in.
+    pub fn to_import_json(&self) -> serde_json::Result<Vec<u8>> {
+        serialize_as(Some(TimestampForm::Millis), || serde_json::to_vec(self))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DrillDown;
+    use super::ThreadFilter;
+    use crate::model_params::ReasoningEffort;
+    use crate::t3_json::fixture;
+    use crate::time_bucket::DateRange;
+    use crate::timezone::DisplayTimezone;
+    use chrono::NaiveDate;
+    use chrono::Weekday;
+
+    fn message(model: &str, created_at: &str) -> fixture::MessageBuilder {
+        fixture::message("a", "t1")
+            .model(model)
+            .created_at(created_at)
+    }
+
+    #[test]
+    fn chart_buckets_match_in_the_display_timezone() {
+        let utc = DisplayTimezone::Named(chrono_tz::UTC);
+        let tokyo = DisplayTimezone::Named(chrono_tz::Asia::Tokyo);
+        // A Sunday evening in UTC is Monday morning in Tokyo
+        let message = message("m1", "2025-01-05T22:45:00Z").build();
+        let range = DateRange {
+            start: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            end: NaiveDate::from_ymd_opt(2025, 1, 31).unwrap(),
+        };
+
+        let sunday = DrillDown::Weekday {
+            weekday: Weekday::Sun,
+            range,
+        };
+        assert!(sunday.matches(&message, utc));
+        assert!(!sunday.matches(&message, tokyo));
+
+        let evening = DrillDown::TimeOfDay { bin: 45, range };
+        assert!(evening.matches(&message, utc));
+
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let month = |model: Option<&str>| DrillDown::Period {
+            start,
+            end: NaiveDate::from_ymd_opt(2025, 2, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap(),
+            label: "2025-01".to_string(),
+            model: model.map(str::to_string),
+        };
+        assert!(month(None).matches(&message, utc));
+        assert!(month(Some("m1")).matches(&message, utc));
+        assert!(!month(Some("m2")).matches(&message, utc));
+    }
+
+    #[test]
+    fn subsets_keep_whole_threads_and_timestamp_forms() {
+        let t3_json = fixture::export(
+            [fixture::thread("t1")
+                .set("created_at", 1736114400000i64)
+                .set("updated_at", "2025-01-05T22:00:00Z")
+                .set("last_message_at", 1736117160000i64)],
+            [
+                message("m1", "2025-01-05T22:45:00Z"),
+                message("m2", "2025-01-05T22:46:00Z"),
+            ],
+        );
+        let filter = ThreadFilter {
+            models: ["m1".to_string()].into(),
+            ..Default::default()
+        };
+        let view = filter.apply(&t3_json);
+        assert_eq!(view.messages.len(), 1);
+
+        let subset = view.complete_threads(&t3_json);
+        // Serializing keeps each timestamp's form, importing needs milliseconds throughout
+        let kept = serde_json::to_value(&subset).unwrap();
+        assert_eq!(kept["threads"][0]["updated_at"], "2025-01-05T22:00:00Z");
+        let import: serde_json::Value =
+            serde_json::from_slice(&subset.to_import_json().unwrap()).unwrap();
+        assert_eq!(import["messages"].as_array().map(Vec::len), Some(2));
+        assert_eq!(import["threads"][0]["created_at"], 1736114400000i64);
+        assert_eq!(import["threads"][0]["updated_at"], 1736114400000i64);
+        assert_eq!(import["messages"][0]["created_at"], 1736117100000i64);
+    }
+
+    #[test]
+    fn facets_on_model_params() {
+        let with_params = |params: serde_json::Value| {
+            message("m1", "2025-01-05T22:45:00Z")
+                .set("modelParams", params)
+                .build()
+        };
+        let messages = [
+            with_params(serde_json::json!({ "reasoningEffort": "high", "includeSearch": true })),
+            with_params(serde_json::json!({ "reasoningEffort": "low" })),
+            with_params(serde_json::Value::Null),
+        ];
+        let kept = |filter: &ThreadFilter| {
+            messages
+                .iter()
+                .map(|message| filter.keeps_message(message))
+                .collect::<Vec<_>>()
+        };
+
+        let high_or_unset = ThreadFilter {
+            reasoning_efforts: [Some(ReasoningEffort::High), None].into(),
+            ..Default::default()
+        };
+        assert_eq!(kept(&high_or_unset), [true, false, true]);
+        let without_search = ThreadFilter {
+            web_search: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(kept(&without_search), [false, true, true]);
+
+        let range = DateRange {
+            start: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            end: NaiveDate::from_ymd_opt(2025, 1, 31).unwrap(),
+        };
+        let low = DrillDown::ReasoningEffort {
+            effort: Some(ReasoningEffort::Low),
+            range,
+        };
+        let utc = DisplayTimezone::Named(chrono_tz::UTC);
+        assert!(!low.matches(&messages[0], utc));
+        assert!(low.matches(&messages[1], utc));
+    }
+
+    #[test]
+    fn ticked_threads_only_key_caches_when_selected_only() {
+        let ticked = ThreadFilter {
+            selected_threads: ["t1".to_string()].into(),
+            ..Default::default()
+        };
+        assert_eq!(ticked.effective(), ThreadFilter::default());
+
+        let selected_only = ThreadFilter {
+            selected_only: true,
+            ..ticked
+        };
+        assert_eq!(selected_only.effective(), selected_only);
+    }
+}
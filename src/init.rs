@@ -9,6 +9,7 @@ pub fn init() -> eyre::Result<()> {
         .with_file(true)
         .with_line_number(true)
         .without_time()
+        .with_writer(std::io::stderr)
         .init();
 
     Ok(())
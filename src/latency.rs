@@ -0,0 +1,266 @@
+// src/latency.rs
+
+use chrono::DateTime;
+use chrono::Utc;
+use eframe::egui;
+use egui_extras::Column;
+use egui_extras::TableBuilder;
+use std::collections::HashMap;
+
+use crate::filter::DrillDown;
+use crate::filter::FilteredView;
+use crate::filter::ThreadFilter;
+use crate::stats::format_seconds;
+use crate::stats::quantile;
+use crate::t3_json::T3Message;
+use crate::t3_json::T3MessageRole;
+use crate::timezone::DisplayTimezone;
+
+/// A user message paired with the assistant message that answered it.
+pub struct ResponseLatency<'a> {
+    pub prompt: &'a T3Message,
+    pub response: &'a T3Message,
+    /// Time between the prompt and the response being created.
+    pub seconds: f64,
+}
+
+/// Pairs each user message with the next assistant message in the same thread.
+///
+/// When several user messages are sent in a row, each of them is paired with the reply that
+/// follows, so earlier prompts count the time until that reply. Pairs whose response was
+/// created before the prompt (clock skew, edited history) are skipped.
+pub fn response_latencies<'a>(messages: &[&'a T3Message]) -> Vec<ResponseLatency<'a>> {
+    let mut by_thread: HashMap<&str, Vec<&T3Message>> = HashMap::new();
+    for message in messages {
+        by_thread
+            .entry(message.thread_id.as_str())
+            .or_default()
+            .push(message);
+    }
+
+    let mut latencies = vec![];
+    for thread_messages in by_thread.values_mut() {
+        thread_messages.sort_by_key(|m| m.created_at);
+        let mut pending_prompts: Vec<&T3Message> = vec![];
+        for message in thread_messages.iter() {
+            match message.role {
+                T3MessageRole::User => pending_prompts.push(message),
+                T3MessageRole::Assistant => {
+                    for prompt in pending_prompts.drain(..) {
+                        let seconds = (*message.created_at - *prompt.created_at).num_milliseconds()
+                            as f64
+                            / 1000.0;
+                        if seconds >= 0.0 {
+                            latencies.push(ResponseLatency {
+                                prompt,
+                                response: message,
+                                seconds,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+    latencies
+}
+
+/// Columns of the slowest responses table that can be sorted on.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+enum LatencyColumn {
+    Thread,
+    Model,
+    PromptAt,
+    #[default]
+    Latency,
+}
+
+/// A row of the slowest responses table. Owned so it can be cached between frames.
+struct LatencyRow {
+    thread_id: String,
+    thread_title: String,
+    model: String,
+    prompt_at: DateTime<Utc>,
+    seconds: f64,
+}
+
+/// State for the slowest responses table of a file.
+pub struct LatencyState {
+    sort_column: LatencyColumn,
+    descending: bool,
+    /// Rows computed for a filter, kept sorted by the current column.
+    rows: Option<(ThreadFilter, Vec<LatencyRow>)>,
+}
+
+impl Default for LatencyState {
+    fn default() -> Self {
+        Self {
+            sort_column: LatencyColumn::default(),
+            descending: true,
+            rows: None,
+        }
+    }
+}
+
+impl LatencyState {
+    fn sort_rows(&mut self) {
+        let Some((_, rows)) = &mut self.rows else {
+            return;
+        };
+        match self.sort_column {
+            LatencyColumn::Thread => rows.sort_by(|a, b| a.thread_title.cmp(&b.thread_title)),
+            LatencyColumn::Model => rows.sort_by(|a, b| a.model.cmp(&b.model)),
+            LatencyColumn::PromptAt => rows.sort_by_key(|row| row.prompt_at),
+            LatencyColumn::Latency => rows.sort_by(|a, b| a.seconds.total_cmp(&b.seconds)),
+        }
+        if self.descending {
+            rows.reverse();
+        }
+    }
+
+    /// Draws a sortable table of response latencies, slowest first by default.
+    /// Clicking a thread title returns a drill-down to that thread.
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        view: &FilteredView,
+        filter: &ThreadFilter,
+        timezone: DisplayTimezone,
+    ) -> Option<DrillDown> {
+        if self
+            .rows
+            .as_ref()
+            .is_none_or(|(cached, _)| cached != filter)
+        {
+            let titles: HashMap<&str, &str> = view
+                .threads
+                .iter()
+                .map(|t| (t.id.as_str(), t.title.as_str()))
+                .collect();
+            let rows = response_latencies(&view.messages)
+                .into_iter()
+                .map(|latency| LatencyRow {
+                    thread_id: latency.prompt.thread_id.clone(),
+                    thread_title: titles
+                        .get(latency.prompt.thread_id.as_str())
+                        .copied()
+                        .unwrap_or_default()
+                        .to_string(),
+                    model: latency.response.model.clone(),
+                    prompt_at: *latency.prompt.created_at,
+                    seconds: latency.seconds,
+                })
+                .collect();
+            self.rows = Some((filter.clone(), rows));
+            self.sort_rows();
+        }
+        let Some((_, rows)) = &self.rows else {
+            return None;
+        };
+
+        let mut seconds: Vec<f64> = rows.iter().map(|row| row.seconds).collect();
+        seconds.sort_by(f64::total_cmp);
+        ui.label(format!(
+            "{} responses, median {}, p90 {}",
+            seconds.len(),
+            format_seconds(quantile(&seconds, 0.5)),
+            format_seconds(quantile(&seconds, 0.9)),
+        ));
+
+        let mut clicked_header = None;
+        let mut drill_down = None;
+        TableBuilder::new(ui)
+            .id_salt("slowest_responses")
+            .striped(true)
+            .max_scroll_height(300.0)
+            .column(Column::remainder().at_least(200.0).clip(true))
+            .column(Column::auto())
+            .column(Column::auto())
+            .column(Column::auto())
+            .header(20.0, |mut header| {
+                for (column, title) in [
+                    (LatencyColumn::Thread, "Thread"),
+                    (LatencyColumn::Model, "Model"),
+                    (LatencyColumn::PromptAt, "Prompt At"),
+                    (LatencyColumn::Latency, "Latency"),
+                ] {
+                    header.col(|ui| {
+                        let arrow = match (self.sort_column == column, self.descending) {
+                            (true, true) => " ⏷",
+                            (true, false) => " ⏶",
+                            (false, _) => "",
+                        };
+                        if ui.button(format!("{}{}", title, arrow)).clicked() {
+                            clicked_header = Some(column);
+                        }
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(18.0, rows.len(), |mut row| {
+                    let latency = &rows[row.index()];
+                    row.col(|ui| {
+                        if ui.link(&latency.thread_title).clicked() {
+                            drill_down = Some(DrillDown::Thread {
+                                id: latency.thread_id.clone(),
+                                title: latency.thread_title.clone(),
+                            });
+                        }
+                    });
+                    row.col(|ui| {
+                        ui.label(&latency.model);
+                    });
+                    row.col(|ui| {
+                        ui.label(timezone.format(latency.prompt_at));
+                    });
+                    row.col(|ui| {
+                        ui.label(format_seconds(latency.seconds));
+                    });
+                });
+            });
+
+        if let Some(column) = clicked_header {
+            if self.sort_column == column {
+                self.descending = !self.descending;
+            } else {
+                self.sort_column = column;
+                self.descending = column == LatencyColumn::Latency;
+            }
+            self.sort_rows();
+        }
+        drill_down
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::response_latencies;
+    use crate::t3_json::T3Message;
+    use crate::t3_json::fixture;
+
+    fn message(id: &str, thread_id: &str, role: &str, created_at: i64) -> T3Message {
+        fixture::message(id, thread_id)
+            .role(role)
+            .created_at(fixture::START + created_at)
+            .build()
+    }
+
+    #[test]
+    fn pairs_every_prompt_with_next_response() {
+        let messages = [
+            message("a", "t1", "user", 1_000),
+            message("b", "t1", "user", 2_000),
+            message("c", "t1", "assistant", 5_000),
+            message("d", "t2", "assistant", 6_000),
+            message("e", "t2", "user", 7_000),
+        ];
+        let messages: Vec<&T3Message> = messages.iter().collect();
+        let latencies = response_latencies(&messages);
+        assert_eq!(latencies.len(), 2);
+        assert_eq!(latencies[0].prompt.id, "a");
+        assert_eq!(latencies[0].seconds, 4.0);
+        assert_eq!(latencies[1].prompt.id, "b");
+        assert_eq!(latencies[1].response.id, "c");
+        assert_eq!(latencies[1].seconds, 3.0);
+    }
+}
@@ -0,0 +1,223 @@
+// src/line_mode.rs
+
+use chrono::DateTime;
+use chrono::Datelike;
+use chrono::NaiveDate;
+use chrono::Utc;
+use egui_plot::PlotPoint;
+use std::collections::BTreeMap;
+
+use crate::charts::Series;
+use crate::time_bucket::TimeBucket;
+
+/// Year the points of a year-over-year overlay are moved into. A leap year, so February 29th fits.
+const OVERLAY_YEAR: i32 = 2000;
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// How the series of a line chart are transformed before drawing.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum LineMode {
+    /// The values as aggregated.
+    #[default]
+    PerBucket,
+    /// Running total from the start of the range.
+    Cumulative,
+    /// Mean of the buckets starting in the trailing 7 days.
+    Rolling7,
+    /// Mean of the buckets starting in the trailing 30 days.
+    Rolling30,
+    /// One line per calendar year, laid over the same January to December axis.
+    YearOverYear,
+}
+
+impl LineMode {
+    /// Returns a human-readable name for the mode.
+    pub fn name(&self) -> &'static str {
+        match self {
+            LineMode::PerBucket => "Per bucket",
+            LineMode::Cumulative => "Cumulative",
+            LineMode::Rolling7 => "7-day average",
+            LineMode::Rolling30 => "30-day average",
+            LineMode::YearOverYear => "Year over year",
+        }
+    }
+
+    /// Returns the modes that make sense for counts.
+    pub fn for_counts() -> &'static [LineMode] {
+        &[
+            LineMode::PerBucket,
+            LineMode::Cumulative,
+            LineMode::Rolling7,
+            LineMode::Rolling30,
+            LineMode::YearOverYear,
+        ]
+    }
+
+    /// Returns the modes that make sense for averages such as latencies, which can't be summed.
+    pub fn for_averages() -> &'static [LineMode] {
+        &[
+            LineMode::PerBucket,
+            LineMode::Rolling7,
+            LineMode::Rolling30,
+            LineMode::YearOverYear,
+        ]
+    }
+
+    /// Returns the `strftime` format for x axis labels. Overlays drop the year, since every
+    /// line shares the same one.
+    pub fn time_format(&self, bucket: TimeBucket) -> &'static str {
+        match (self, bucket) {
+            (LineMode::YearOverYear, TimeBucket::Hour) => "%b %d %H:00",
+            (LineMode::YearOverYear, TimeBucket::Day | TimeBucket::Week) => "%b %d",
+            (LineMode::YearOverYear, TimeBucket::Month | TimeBucket::Year) => "%b",
+            _ => bucket.label_format(),
+        }
+    }
+
+    /// Transforms series whose x values are seconds since the epoch, ordered by x.
+    pub fn apply(&self, series: &[Series<PlotPoint>]) -> Vec<Series<PlotPoint>> {
+        match self {
+            LineMode::PerBucket => series.to_vec(),
+            LineMode::Cumulative => series
+                .iter()
+                .map(|s| {
+                    let mut total = 0.0;
+                    let values = s
+                        .values
+                        .iter()
+                        .map(|point| {
+                            total += point.y;
+                            PlotPoint::new(point.x, total)
+                        })
+                        .collect();
+                    Series {
+                        name: s.name.clone(),
+                        values,
+                    }
+                })
+                .collect(),
+            LineMode::Rolling7 => series.iter().map(|s| rolling_mean(s, 7.0)).collect(),
+            LineMode::Rolling30 => series.iter().map(|s| rolling_mean(s, 30.0)).collect(),
+            LineMode::YearOverYear => series
+                .iter()
+                .flat_map(|s| {
+                    let single = series.len() == 1;
+                    split_by_year(&s.values)
+                        .into_iter()
+                        .map(move |(year, values)| Series {
+                            name: if single {
+                                year.to_string()
+                            } else {
+                                format!("{} {}", s.name, year)
+                            },
+                            values,
+                        })
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Replaces each point with the mean of the points within the trailing window, itself included.
+fn rolling_mean(series: &Series<PlotPoint>, days: f64) -> Series<PlotPoint> {
+    let window = days * SECONDS_PER_DAY;
+    let values = &series.values;
+    let mut start = 0;
+    let mut sum = 0.0;
+    let mut averaged = Vec::with_capacity(values.len());
+    for (end, point) in values.iter().enumerate() {
+        sum += point.y;
+        while values[start].x <= point.x - window {
+            sum -= values[start].y;
+            start += 1;
+        }
+        averaged.push(PlotPoint::new(point.x, sum / (end + 1 - start) as f64));
+    }
+    Series {
+        name: series.name.clone(),
+        values: averaged,
+    }
+}
+
+/// Groups points by year, moving each into `OVERLAY_YEAR` so the years line up.
+fn split_by_year(values: &[PlotPoint]) -> BTreeMap<i32, Vec<PlotPoint>> {
+    let mut by_year: BTreeMap<i32, Vec<PlotPoint>> = BTreeMap::new();
+    for point in values {
+        let Some(date_time) = DateTime::<Utc>::from_timestamp(point.x.round() as i64, 0) else {
+            continue;
+        };
+        let date_time = date_time.naive_utc();
+        let Some(date) = NaiveDate::from_ymd_opt(OVERLAY_YEAR, date_time.month(), date_time.day())
+        else {
+            continue;
+        };
+        let x = date.and_time(date_time.time()).and_utc().timestamp() as f64;
+        by_year
+            .entry(date_time.year())
+            .or_default()
+            .push(PlotPoint::new(x, point.y));
+    }
+    by_year
+}
+
+#[cfg(test)]
+mod test {
+    use super::LineMode;
+    use super::SECONDS_PER_DAY;
+    use crate::charts::Series;
+    use chrono::NaiveDate;
+    use egui_plot::PlotPoint;
+
+    fn daily(values: &[f64]) -> Vec<Series<PlotPoint>> {
+        vec![Series {
+            name: "Messages".to_string(),
+            values: values
+                .iter()
+                .enumerate()
+                .map(|(day, y)| PlotPoint::new(day as f64 * SECONDS_PER_DAY, *y))
+                .collect(),
+        }]
+    }
+
+    fn ys(series: &Series<PlotPoint>) -> Vec<f64> {
+        series.values.iter().map(|point| point.y).collect()
+    }
+
+    #[test]
+    fn cumulative_sums_in_order() {
+        let series = LineMode::Cumulative.apply(&daily(&[1.0, 0.0, 2.0]));
+        assert_eq!(ys(&series[0]), vec![1.0, 1.0, 3.0]);
+    }
+
+    #[test]
+    fn rolling_mean_uses_trailing_window() {
+        let values: Vec<f64> = (1..=9).map(f64::from).collect();
+        let series = LineMode::Rolling7.apply(&daily(&values));
+        // Days 1-7 average to 4, then the window slides by one day at a time
+        assert_eq!(ys(&series[0])[6..], [4.0, 5.0, 6.0]);
+        assert_eq!(ys(&series[0])[0], 1.0);
+    }
+
+    #[test]
+    fn year_over_year_splits_and_aligns_years() {
+        let at = |year, month, day| {
+            NaiveDate::from_ymd_opt(year, month, day)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+                .and_utc()
+                .timestamp() as f64
+        };
+        let series = vec![Series {
+            name: "Messages".to_string(),
+            values: vec![
+                PlotPoint::new(at(2024, 3, 1), 1.0),
+                PlotPoint::new(at(2025, 3, 1), 2.0),
+            ],
+        }];
+        let series = LineMode::YearOverYear.apply(&series);
+        let names: Vec<&str> = series.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["2024", "2025"]);
+        assert_eq!(series[0].values[0].x, series[1].values[0].x);
+    }
+}
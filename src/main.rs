@@ -1,15 +1,34 @@
 // src/main.rs
 
 mod app;
+pub mod attachment;
+pub mod chart_export;
 pub mod charts;
+mod cli;
+pub mod comparison;
+pub mod cost;
+pub mod dump;
+pub mod filter;
 mod init;
+pub mod latency;
+pub mod line_mode;
+pub mod model_params;
+pub mod query;
+pub mod reliability;
+pub mod sqlite;
+pub mod stats;
+pub mod summary;
 pub mod t3_json;
 pub mod t3_timestamp;
+pub mod tabular;
+pub mod time_bucket;
+pub mod timezone;
 
 use app::MyApp;
+use clap::Parser;
+use cli::Cli;
 use eframe::egui;
 use eyre::Result;
-use std::env;
 use std::time::Duration;
 use tokio::runtime::Runtime;
 use tracing::error;
@@ -17,11 +36,15 @@ use tracing::info;
 
 fn main() -> Result<()> {
     init::init()?;
+    let cli = Cli::parse();
+    if let Some(command) = cli.command {
+        return command.run();
+    }
     info!("Ahoy!");
 
     // 1) Create a Tokio runtime
     let rt = Runtime::new()?;
-    let initial_paths = env::args_os().skip(1).map(Into::into).collect();
+    let initial_paths = cli.paths;
 
     // 2) Keep the runtime alive in a separate thread:
     std::thread::spawn({
@@ -0,0 +1,209 @@
+// src/model_params.rs
+
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+use serde_json::Value;
+
+use crate::t3_json::T3Message;
+
+/// Label for messages sent without a reasoning effort.
+const NOT_SET: &str = "not set";
+
+/// How much reasoning the model was asked to do. Levels added by newer versions of t3.chat
+/// are kept by name.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum ReasoningEffort {
+    Low,
+    Medium,
+    High,
+    Other(String),
+}
+
+impl ReasoningEffort {
+    /// Returns the name as written in exports.
+    pub fn name(&self) -> &str {
+        match self {
+            ReasoningEffort::Low => "low",
+            ReasoningEffort::Medium => "medium",
+            ReasoningEffort::High => "high",
+            ReasoningEffort::Other(name) => name,
+        }
+    }
+
+    /// Returns the name of a message's effort for charts and facets, "not set" when missing.
+    pub fn label(effort: Option<&ReasoningEffort>) -> &str {
+        effort.map_or(NOT_SET, ReasoningEffort::name)
+    }
+
+    /// Reverses `label`.
+    pub fn from_label(label: &str) -> Option<ReasoningEffort> {
+        (label != NOT_SET).then(|| ReasoningEffort::from(label.to_string()))
+    }
+}
+
+impl From<String> for ReasoningEffort {
+    fn from(name: String) -> Self {
+        match name.as_str() {
+            "low" => ReasoningEffort::Low,
+            "medium" => ReasoningEffort::Medium,
+            "high" => ReasoningEffort::High,
+            _ => ReasoningEffort::Other(name),
+        }
+    }
+}
+
+impl From<ReasoningEffort> for String {
+    fn from(effort: ReasoningEffort) -> Self {
+        effort.name().to_string()
+    }
+}
+
+/// The settings a message was sent with. The original JSON is kept so saving an export writes
+/// it back exactly as it was loaded; a known key holding a value of an unexpected type reads
+/// as unset rather than failing the whole export.
+#[derive(Debug, Clone, PartialEq)]
+pub struct T3ModelParams {
+    pub reasoning_effort: Option<ReasoningEffort>,
+    /// Whether web search was enabled.
+    pub include_search: Option<bool>,
+    pub temperature: Option<f64>,
+    raw: Value,
+}
+
+impl T3ModelParams {
+    pub fn from_value(value: Value) -> Self {
+        Self {
+            reasoning_effort: value
+                .get("reasoningEffort")
+                .and_then(Value::as_str)
+                .map(|name| ReasoningEffort::from(name.to_string())),
+            include_search: value.get("includeSearch").and_then(Value::as_bool),
+            temperature: value.get("temperature").and_then(Value::as_f64),
+            raw: value,
+        }
+    }
+
+    /// Describes each set parameter, e.g. `["reasoning: high", "web search"]`.
+    /// Unknown keys, and known ones that couldn't be read, are shown with their JSON value.
+    pub fn labels(&self) -> Vec<String> {
+        let mut labels = vec![];
+        if let Some(effort) = &self.reasoning_effort {
+            labels.push(format!("reasoning: {}", effort.name()));
+        }
+        if self.include_search == Some(true) {
+            labels.push("web search".to_string());
+        }
+        if let Some(temperature) = self.temperature {
+            labels.push(format!("temperature: {}", temperature));
+        }
+        let Some(object) = self.raw.as_object() else {
+            if !self.raw.is_null() {
+                labels.push(self.raw.to_string());
+            }
+            return labels;
+        };
+        for (key, value) in object {
+            let read = match key.as_str() {
+                "reasoningEffort" => self.reasoning_effort.is_some(),
+                "includeSearch" => self.include_search.is_some(),
+                "temperature" => self.temperature.is_some(),
+                _ => false,
+            };
+            if !read && !value.is_null() {
+                labels.push(format!("{}: {}", key, value));
+            }
+        }
+        labels
+    }
+}
+
+impl Serialize for T3ModelParams {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.raw.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for T3ModelParams {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Value::deserialize(deserializer).map(T3ModelParams::from_value)
+    }
+}
+
+/// Returns the reasoning effort the message was sent with, if any.
+pub fn reasoning_effort(message: &T3Message) -> Option<&ReasoningEffort> {
+    message.model_params.as_ref()?.reasoning_effort.as_ref()
+}
+
+/// Returns true when the message was sent with web search enabled.
+pub fn used_search(message: &T3Message) -> bool {
+    message
+        .model_params
+        .as_ref()
+        .is_some_and(|params| params.include_search == Some(true))
+}
+
+#[cfg(test)]
+mod test {
+    use super::ReasoningEffort;
+    use super::T3ModelParams;
+    use serde_json::json;
+
+    #[test]
+    fn reads_known_fields_and_keeps_the_rest() {
+        let raw = json!({
+            "reasoningEffort": "high",
+            "includeSearch": true,
+            "temperature": 0.5,
+            "topK": 40,
+        });
+        let params: T3ModelParams = serde_json::from_value(raw.clone()).unwrap();
+        assert_eq!(params.reasoning_effort, Some(ReasoningEffort::High));
+        assert_eq!(params.include_search, Some(true));
+        assert_eq!(
+            params.labels(),
+            vec![
+                "reasoning: high",
+                "web search",
+                "temperature: 0.5",
+                "topK: 40"
+            ]
+        );
+        assert_eq!(serde_json::to_value(&params).unwrap(), raw);
+
+        let params: T3ModelParams =
+            serde_json::from_value(json!({ "reasoningEffort": "minimal" })).unwrap();
+        assert_eq!(
+            params.reasoning_effort,
+            Some(ReasoningEffort::Other("minimal".to_string()))
+        );
+        assert_eq!(
+            serde_json::to_value(&params).unwrap(),
+            json!({ "reasoningEffort": "minimal" })
+        );
+    }
+
+    #[test]
+    fn tolerates_unexpected_types_and_keeps_nulls() {
+        let raw = json!({
+            "reasoningEffort": null,
+            "includeSearch": "yes",
+            "temperature": "0.7",
+        });
+        let params: T3ModelParams = serde_json::from_value(raw.clone()).unwrap();
+        assert_eq!(params.reasoning_effort, None);
+        assert_eq!(params.include_search, None);
+        assert_eq!(params.temperature, None);
+        assert_eq!(
+            params.labels(),
+            vec!["includeSearch: \"yes\"", "temperature: \"0.7\""]
+        );
+        assert_eq!(serde_json::to_value(&params).unwrap(), raw);
+
+        let params: T3ModelParams = serde_json::from_value(json!("fast")).unwrap();
+        assert_eq!(params.reasoning_effort, None);
+        assert_eq!(serde_json::to_value(&params).unwrap(), json!("fast"));
+    }
+}
@@ -0,0 +1,324 @@
+// src/query.rs
+
+use eframe::egui;
+use egui_extras::Column;
+use egui_extras::TableBuilder;
+use rusqlite::Connection;
+use rusqlite::types::ValueRef;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::sqlite;
+use crate::t3_json::T3Json;
+
+/// Rows shown per query; the rest are dropped so huge results don't stall the UI.
+const MAX_ROWS: usize = 1000;
+
+const DEFAULT_QUERY: &str = "SELECT t.id, t.title, COUNT(*) AS messages
+FROM threads t JOIN messages m ON m.thread_id = t.id
+GROUP BY t.id
+ORDER BY messages DESC
+LIMIT 20";
+
+/// The rows returned by a query, formatted for display.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    /// True when more than `MAX_ROWS` rows matched.
+    pub truncated: bool,
+}
+
+/// Runs a single statement and formats every value as text.
+pub fn run_query(connection: &Connection, sql: &str) -> rusqlite::Result<QueryResult> {
+    let mut statement = connection.prepare(sql)?;
+    let columns: Vec<String> = statement
+        .column_names()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    let mut rows = vec![];
+    let mut truncated = false;
+    let mut query = statement.query([])?;
+    while let Some(row) = query.next()? {
+        if rows.len() == MAX_ROWS {
+            truncated = true;
+            break;
+        }
+        let values = (0..columns.len())
+            .map(|index| row.get_ref(index).map(format_value))
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        rows.push(values);
+    }
+    Ok(QueryResult {
+        columns,
+        rows,
+        truncated,
+    })
+}
+
+fn format_value(value: ValueRef) -> String {
+    match value {
+        ValueRef::Null => "NULL".to_string(),
+        ValueRef::Integer(value) => value.to_string(),
+        ValueRef::Real(value) => value.to_string(),
+        ValueRef::Text(text) => String::from_utf8_lossy(text).into_owned(),
+        ValueRef::Blob(blob) => format!("<{} bytes>", blob.len()),
+    }
+}
+
+/// An in-memory database holding every loaded export, and the exports it was built from.
+struct QueryDatabase {
+    sources: Vec<Arc<T3Json>>,
+    connection: Connection,
+}
+
+impl QueryDatabase {
+    /// Combines the exports into one database with the tables written by `sqlite::write`.
+    fn build(sources: &[Arc<T3Json>]) -> eyre::Result<Self> {
+        let exports: Vec<&T3Json> = sources.iter().map(|t3_json| t3_json.as_ref()).collect();
+        let mut connection = Connection::open_in_memory()?;
+        sqlite::insert(&mut connection, &exports)?;
+        info!("Built query database from {} exports", exports.len());
+        Ok(Self {
+            sources: sources.to_vec(),
+            connection,
+        })
+    }
+
+    fn is_built_from(&self, sources: &[Arc<T3Json>]) -> bool {
+        self.sources.len() == sources.len()
+            && self
+                .sources
+                .iter()
+                .zip(sources)
+                .all(|(a, b)| Arc::ptr_eq(a, b))
+    }
+}
+
+/// A query to run in the background. The database built by the previous run travels with
+/// the job and comes back with the result, so it is only rebuilt when the exports changed.
+pub struct QueryJob {
+    sql: String,
+    sources: Vec<Arc<T3Json>>,
+    database: Option<QueryDatabase>,
+}
+
+/// The result of a `QueryJob`, with the database to keep for the next run.
+pub struct QueryOutcome {
+    database: Option<QueryDatabase>,
+    result: Result<QueryResult, String>,
+}
+
+impl QueryJob {
+    /// Runs the query, first rebuilding the database if the loaded exports changed.
+    pub fn run(self) -> QueryOutcome {
+        let database = match self.database {
+            Some(database) if database.is_built_from(&self.sources) => database,
+            _ => match QueryDatabase::build(&self.sources) {
+                Ok(database) => database,
+                Err(e) => {
+                    return QueryOutcome {
+                        database: None,
+                        result: Err(format!("{:#}", e)),
+                    };
+                }
+            },
+        };
+        let result = run_query(&database.connection, &self.sql).map_err(|e| e.to_string());
+        QueryOutcome {
+            database: Some(database),
+            result,
+        }
+    }
+}
+
+/// State for the window running SQL over the loaded exports.
+pub struct QueryState {
+    pub sql: String,
+    database: Option<QueryDatabase>,
+    result: Option<Result<QueryResult, String>>,
+    /// Query waiting to be picked up by `take_job`.
+    job: Option<QueryJob>,
+    /// True from queuing a job until its outcome is received.
+    running: bool,
+}
+
+impl Default for QueryState {
+    fn default() -> Self {
+        Self {
+            sql: DEFAULT_QUERY.to_string(),
+            database: None,
+            result: None,
+            job: None,
+            running: false,
+        }
+    }
+}
+
+impl QueryState {
+    /// Queues the query, handing the current database to the job.
+    fn run(&mut self, sources: &[Arc<T3Json>]) {
+        self.job = Some(QueryJob {
+            sql: self.sql.clone(),
+            sources: sources.to_vec(),
+            database: self.database.take(),
+        });
+        self.running = true;
+    }
+
+    /// Returns the query queued since the last call, for the caller to run in the background.
+    pub fn take_job(&mut self) -> Option<QueryJob> {
+        self.job.take()
+    }
+
+    /// Stores the result of a job and keeps its database for the next run.
+    pub fn receive(&mut self, outcome: QueryOutcome) {
+        self.database = outcome.database;
+        self.result = Some(outcome.result);
+        self.running = false;
+    }
+
+    /// Draws the editor and the results, with a spinner while a query runs. Values that are
+    /// ids of loaded threads are links; clicking one returns that thread id.
+    pub fn draw(&mut self, ui: &mut egui::Ui, sources: &[Arc<T3Json>]) -> Option<String> {
+        ui.label(
+            "Tables: threads, messages, and messages_fts for full-text search \
+            (e.g. WHERE messages_fts MATCH 'rust'). Timestamps are milliseconds since the epoch.",
+        );
+        let editor = ui.add(
+            egui::TextEdit::multiline(&mut self.sql)
+                .code_editor()
+                .desired_rows(6)
+                .desired_width(f32::INFINITY),
+        );
+        let run_shortcut = editor.has_focus()
+            && ui.input(|i| i.modifiers.command && i.key_pressed(egui::Key::Enter));
+        ui.horizontal(|ui| {
+            let run = ui.add_enabled(!self.running, egui::Button::new("Run"));
+            if !self.running && (run.clicked() || run_shortcut) {
+                self.run(sources);
+            }
+            if self.running {
+                ui.spinner();
+                ui.label("Running query…");
+            } else {
+                ui.label("Ctrl+Enter runs the query.");
+            }
+        });
+        ui.separator();
+
+        let result = match &self.result {
+            None => return None,
+            Some(Err(e)) => {
+                ui.colored_label(ui.visuals().error_fg_color, e);
+                return None;
+            }
+            Some(Ok(result)) => result,
+        };
+        ui.label(if result.truncated {
+            format!("Showing the first {} rows", result.rows.len())
+        } else {
+            format!("{} rows", result.rows.len())
+        });
+        if result.columns.is_empty() {
+            return None;
+        }
+
+        let thread_ids: HashSet<&str> = sources
+            .iter()
+            .flat_map(|t3_json| &t3_json.threads)
+            .map(|thread| thread.id.as_str())
+            .collect();
+        let mut clicked = None;
+        egui::ScrollArea::horizontal().show(ui, |ui| {
+            TableBuilder::new(ui)
+                .id_salt("query_results")
+                .striped(true)
+                .max_scroll_height(400.0)
+                .columns(
+                    Column::auto().at_least(60.0).at_most(400.0).clip(true),
+                    result.columns.len(),
+                )
+                .header(20.0, |mut header| {
+                    for column in &result.columns {
+                        header.col(|ui| {
+                            ui.strong(column);
+                        });
+                    }
+                })
+                .body(|body| {
+                    body.rows(18.0, result.rows.len(), |mut row| {
+                        for value in &result.rows[row.index()] {
+                            row.col(|ui| {
+                                if thread_ids.contains(value.as_str()) {
+                                    if ui.link(value).on_hover_text("Open thread").clicked() {
+                                        clicked = Some(value.clone());
+                                    }
+                                } else {
+                                    ui.label(value);
+                                }
+                            });
+                        }
+                    });
+                });
+        });
+        clicked
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::QueryDatabase;
+    use super::QueryState;
+    use super::run_query;
+    use crate::t3_json::fixture;
+    use std::sync::Arc;
+
+    #[test]
+    fn queries_combined_exports() -> eyre::Result<()> {
+        let export = |thread_id: &str, content: &str| {
+            Arc::new(fixture::export(
+                [],
+                [fixture::message(&format!("{}-m", thread_id), thread_id).content(content)],
+            ))
+        };
+        let sources = [export("t1", "hello"), export("t2", "world")];
+        let database = QueryDatabase::build(&sources)?;
+        assert!(database.is_built_from(&sources));
+
+        let result = run_query(
+            &database.connection,
+            "SELECT thread_id, NULL AS missing FROM messages ORDER BY thread_id",
+        )?;
+        assert_eq!(result.columns, vec!["thread_id", "missing"]);
+        assert_eq!(result.rows, vec![vec!["t1", "NULL"], vec!["t2", "NULL"]]);
+        assert!(!result.truncated);
+
+        // Jobs hand the database back, and reuse it while the exports are unchanged
+        let mut state = QueryState {
+            sql: "SELECT COUNT(*) FROM messages".to_string(),
+            ..Default::default()
+        };
+        state.run(&sources);
+        let job = state.take_job().expect("a queued job");
+        assert!(state.running && state.take_job().is_none());
+        state.receive(job.run());
+        assert!(!state.running);
+        assert_eq!(
+            state
+                .result
+                .as_ref()
+                .map(|result| result.as_ref().map(|r| &r.rows)),
+            Some(Ok(&vec![vec!["2".to_string()]]))
+        );
+        assert!(
+            state
+                .database
+                .as_ref()
+                .is_some_and(|database| database.is_built_from(&sources))
+        );
+        Ok(())
+    }
+}
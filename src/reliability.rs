@@ -0,0 +1,391 @@
+// src/reliability.rs
+
+use chrono::DateTime;
+use chrono::Utc;
+use eframe::egui;
+use egui_extras::Column;
+use egui_extras::TableBuilder;
+use egui_plot::Legend;
+use egui_plot::Line;
+use egui_plot::Plot;
+use egui_plot::PlotPoint;
+use egui_plot::PlotPoints;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use crate::app::thread_url;
+use crate::filter::DrillDown;
+use crate::filter::FilteredView;
+use crate::filter::ThreadFilter;
+use crate::t3_json::T3Message;
+use crate::t3_json::T3MessageRole;
+use crate::t3_json::T3MessageStatus;
+use crate::time_bucket::DateRange;
+use crate::time_bucket::TimeBucket;
+use crate::time_bucket::bucket_counts;
+use crate::timezone::DisplayTimezone;
+
+/// Outcome counts for a group of assistant messages.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Outcomes {
+    pub total: usize,
+    pub errors: usize,
+    pub cancelled: usize,
+}
+
+impl Outcomes {
+    fn add(&mut self, message: &T3Message) {
+        self.total += 1;
+        match message.status {
+            T3MessageStatus::Error => self.errors += 1,
+            T3MessageStatus::Cancelled => self.cancelled += 1,
+            _ => {}
+        }
+    }
+
+    /// Returns the share of errors, in percent.
+    pub fn error_rate(&self) -> f64 {
+        percent(self.errors, self.total)
+    }
+
+    /// Returns the share of cancelled generations, in percent.
+    pub fn cancel_rate(&self) -> f64 {
+        percent(self.cancelled, self.total)
+    }
+}
+
+fn percent(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64 * 100.0
+    }
+}
+
+/// Returns true for generations that ended in an error or were stopped by the user.
+pub fn is_failed(message: &T3Message) -> bool {
+    matches!(
+        message.status,
+        T3MessageStatus::Error | T3MessageStatus::Cancelled
+    )
+}
+
+/// A thread with at least one failed generation.
+struct FailedThread {
+    thread_id: String,
+    title: String,
+    errors: usize,
+    cancelled: usize,
+    last_failure: DateTime<Utc>,
+}
+
+/// Everything shown by the reliability view, computed once per filter and settings.
+struct ComputedReliability {
+    total: Outcomes,
+    by_model: Vec<(String, Outcomes)>,
+    /// Error and cancel rate per bucket, in percent.
+    over_time: Vec<(String, Vec<PlotPoint>)>,
+    failed_threads: Vec<FailedThread>,
+}
+
+/// State for the reliability view of a file.
+pub struct ReliabilityState {
+    pub bucket: TimeBucket,
+    computed: Option<(
+        (ThreadFilter, TimeBucket, DisplayTimezone),
+        ComputedReliability,
+    )>,
+}
+
+impl Default for ReliabilityState {
+    fn default() -> Self {
+        Self {
+            bucket: TimeBucket::Week,
+            computed: None,
+        }
+    }
+}
+
+impl ReliabilityState {
+    fn compute(&self, view: &FilteredView, timezone: DisplayTimezone) -> ComputedReliability {
+        // Only assistant messages are generations; user messages are always "done"
+        let generations: Vec<&T3Message> = view
+            .messages
+            .iter()
+            .filter(|m| matches!(m.role, T3MessageRole::Assistant))
+            .copied()
+            .collect();
+
+        let mut total = Outcomes::default();
+        let mut by_model: BTreeMap<String, Outcomes> = BTreeMap::new();
+        for message in &generations {
+            total.add(message);
+            by_model
+                .entry(message.model.clone())
+                .or_default()
+                .add(message);
+        }
+
+        let over_time = match DateRange::of_messages(&generations, timezone) {
+            Some(range) => {
+                let all = bucket_counts(&generations, &range, self.bucket, timezone);
+                let rate = |status: fn(&T3Message) -> bool| {
+                    let matching: Vec<&T3Message> =
+                        generations.iter().filter(|m| status(m)).copied().collect();
+                    bucket_counts(&matching, &range, self.bucket, timezone)
+                        .into_iter()
+                        .zip(&all)
+                        .filter(|(_, (_, total))| *total > 0)
+                        .map(|((start, count), (_, total))| {
+                            PlotPoint::new(
+                                start.and_utc().timestamp() as f64,
+                                percent(count, *total),
+                            )
+                        })
+                        .collect()
+                };
+                vec![
+                    (
+                        "Error rate".to_string(),
+                        rate(|m| matches!(m.status, T3MessageStatus::Error)),
+                    ),
+                    (
+                        "Cancel rate".to_string(),
+                        rate(|m| matches!(m.status, T3MessageStatus::Cancelled)),
+                    ),
+                ]
+            }
+            None => vec![],
+        };
+
+        let titles: HashMap<&str, &str> = view
+            .threads
+            .iter()
+            .map(|t| (t.id.as_str(), t.title.as_str()))
+            .collect();
+        let mut failed: HashMap<&str, FailedThread> = HashMap::new();
+        for message in generations.iter().filter(|m| is_failed(m)) {
+            let thread = failed
+                .entry(message.thread_id.as_str())
+                .or_insert_with(|| FailedThread {
+                    thread_id: message.thread_id.clone(),
+                    title: titles
+                        .get(message.thread_id.as_str())
+                        .copied()
+                        .unwrap_or_default()
+                        .to_string(),
+                    errors: 0,
+                    cancelled: 0,
+                    last_failure: *message.created_at,
+                });
+            match message.status {
+                T3MessageStatus::Error => thread.errors += 1,
+                _ => thread.cancelled += 1,
+            }
+            thread.last_failure = thread.last_failure.max(*message.created_at);
+        }
+        let mut failed_threads: Vec<FailedThread> = failed.into_values().collect();
+        failed_threads.sort_by_key(|thread| Reverse(thread.last_failure));
+
+        let mut by_model: Vec<(String, Outcomes)> = by_model.into_iter().collect();
+        by_model.sort_by(|(a_name, a), (b_name, b)| b.total.cmp(&a.total).then(a_name.cmp(b_name)));
+
+        ComputedReliability {
+            total,
+            by_model,
+            over_time,
+            failed_threads,
+        }
+    }
+
+    /// Draws error and cancel rates per model and over time, and the threads with failed
+    /// generations. Clicking a thread returns a drill-down to it.
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        view: &FilteredView,
+        filter: &ThreadFilter,
+        timezone: DisplayTimezone,
+    ) -> Option<DrillDown> {
+        let key = (filter.clone(), self.bucket, timezone);
+        if self
+            .computed
+            .as_ref()
+            .is_none_or(|(cached, _)| *cached != key)
+        {
+            self.computed = Some((key, self.compute(view, timezone)));
+        }
+        let Some((_, computed)) = &self.computed else {
+            return None;
+        };
+
+        let total = computed.total;
+        ui.label(format!(
+            "{} generations: {} errors ({:.1}%), {} cancelled ({:.1}%)",
+            total.total,
+            total.errors,
+            total.error_rate(),
+            total.cancelled,
+            total.cancel_rate(),
+        ));
+
+        TableBuilder::new(ui)
+            .id_salt("reliability_by_model")
+            .striped(true)
+            .max_scroll_height(200.0)
+            .column(Column::auto().at_least(120.0).clip(true))
+            .columns(Column::auto().at_least(60.0), 5)
+            .header(20.0, |mut header| {
+                for title in [
+                    "Model",
+                    "Generations",
+                    "Errors",
+                    "Error %",
+                    "Cancelled",
+                    "Cancel %",
+                ] {
+                    header.col(|ui| {
+                        ui.strong(title);
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(18.0, computed.by_model.len(), |mut row| {
+                    let (model, outcomes) = &computed.by_model[row.index()];
+                    row.col(|ui| {
+                        ui.label(model);
+                    });
+                    for value in [
+                        outcomes.total.to_string(),
+                        outcomes.errors.to_string(),
+                        format!("{:.1}", outcomes.error_rate()),
+                        outcomes.cancelled.to_string(),
+                        format!("{:.1}", outcomes.cancel_rate()),
+                    ] {
+                        row.col(|ui| {
+                            ui.monospace(value);
+                        });
+                    }
+                });
+            });
+
+        ui.horizontal(|ui| {
+            ui.label("Rates per:");
+            egui::ComboBox::from_id_salt("reliability_bucket")
+                .selected_text(self.bucket.name())
+                .show_ui(ui, |ui| {
+                    for bucket in TimeBucket::all() {
+                        ui.selectable_value(&mut self.bucket, *bucket, bucket.name());
+                    }
+                });
+        });
+        let format = self.bucket.label_format();
+        Plot::new("reliability_over_time")
+            .height(200.0)
+            .legend(Legend::default())
+            .y_axis_label("%")
+            .x_axis_formatter(move |x, _range| {
+                DateTime::<Utc>::from_timestamp(x.value.round() as i64, 0)
+                    .map(|date_time| date_time.format(format).to_string())
+                    .unwrap_or_default()
+            })
+            .show(ui, |plot_ui| {
+                for (name, points) in &computed.over_time {
+                    plot_ui.line(Line::new(name, PlotPoints::Borrowed(points)));
+                }
+            });
+
+        let mut drill_down = None;
+        ui.strong(format!(
+            "Threads with failed generations ({})",
+            computed.failed_threads.len()
+        ));
+        TableBuilder::new(ui)
+            .id_salt("failed_threads")
+            .striped(true)
+            .max_scroll_height(300.0)
+            .column(Column::remainder().at_least(200.0).clip(true))
+            .columns(Column::auto(), 4)
+            .header(20.0, |mut header| {
+                for title in ["Thread", "Errors", "Cancelled", "Last Failure", ""] {
+                    header.col(|ui| {
+                        ui.strong(title);
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(18.0, computed.failed_threads.len(), |mut row| {
+                    let thread = &computed.failed_threads[row.index()];
+                    row.col(|ui| {
+                        if ui.link(&thread.title).clicked() {
+                            drill_down = Some(DrillDown::Thread {
+                                id: thread.thread_id.clone(),
+                                title: thread.title.clone(),
+                            });
+                        }
+                    });
+                    row.col(|ui| {
+                        ui.monospace(thread.errors.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.monospace(thread.cancelled.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(timezone.format(thread.last_failure));
+                    });
+                    row.col(|ui| {
+                        if ui.small_button("Open").clicked() {
+                            ui.ctx()
+                                .open_url(egui::OpenUrl::new_tab(thread_url(&thread.thread_id)));
+                        }
+                    });
+                });
+            });
+        drill_down
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ReliabilityState;
+    use crate::filter::ThreadFilter;
+    use crate::t3_json::fixture;
+    use crate::timezone::DisplayTimezone;
+
+    fn message(
+        id: &str,
+        thread_id: &str,
+        role: &str,
+        status: &str,
+        model: &str,
+    ) -> fixture::MessageBuilder {
+        fixture::message(id, thread_id)
+            .role(role)
+            .status(status)
+            .model(model)
+    }
+
+    #[test]
+    fn rates_count_assistant_messages_only() {
+        let t3_json = fixture::export(
+            [],
+            [
+                message("a", "t1", "user", "done", "m1"),
+                message("b", "t1", "assistant", "error", "m1"),
+                message("c", "t1", "assistant", "done", "m1"),
+                message("d", "t2", "assistant", "cancelled", "m2"),
+                message("e", "t2", "assistant", "done", "m2"),
+            ],
+        );
+        let view = ThreadFilter::default().apply(&t3_json);
+        let computed =
+            ReliabilityState::default().compute(&view, DisplayTimezone::Named(chrono_tz::UTC));
+        assert_eq!(computed.total.total, 4);
+        assert_eq!(computed.total.error_rate(), 25.0);
+        assert_eq!(computed.total.cancel_rate(), 25.0);
+        assert_eq!(computed.by_model[0].1.errors, 1);
+        assert_eq!(computed.failed_threads.len(), 2);
+        assert_eq!(computed.over_time[0].1[0].y, 25.0);
+    }
+}
@@ -0,0 +1,291 @@
+// src/sqlite.rs
+
+use chrono::TimeZone;
+use chrono::Utc;
+use eyre::Context;
+use rusqlite::Connection;
+use rusqlite::Error::FromSqlConversionFailure;
+use rusqlite::Row;
+use rusqlite::params;
+use rusqlite::types::Type;
+use rusqlite::types::ValueRef;
+use serde::de::DeserializeOwned;
+use std::path::Path;
+use tracing::info;
+
+use crate::t3_json::T3Json;
+use crate::t3_json::T3Message;
+use crate::t3_json::T3Thread;
+use crate::t3_timestamp::T3Timestamp;
+
+/// The first bytes of every SQLite database file.
+pub const MAGIC: &[u8] = b"SQLite format 3\0";
+
+/// Tables written for an export. Timestamps are milliseconds since the epoch, enums are stored
+/// as their `as_str` names and `model_params` and `attachments` as JSON text.
+/// `messages_fts` indexes message content for `MATCH` queries.
+const SCHEMA: &str = "
+CREATE TABLE threads (
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    user_edited_title INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    model TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER,
+    last_message_at INTEGER NOT NULL
+);
+CREATE TABLE messages (
+    id TEXT PRIMARY KEY,
+    thread_id TEXT NOT NULL,
+    role TEXT NOT NULL,
+    status TEXT NOT NULL,
+    model TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    content TEXT NOT NULL,
+    model_params TEXT,
+    attachments TEXT
+);
+CREATE INDEX messages_thread_id ON messages (thread_id);
+CREATE INDEX messages_created_at ON messages (created_at);
+CREATE VIRTUAL TABLE messages_fts USING fts5 (content, content='messages', content_rowid='rowid');
+";
+
+/// Writes an export into a new SQLite database at `path`, replacing any existing file.
+/// The database is built next to `path` and renamed over it once complete, so a failed
+/// write leaves the existing file untouched.
+pub fn write(t3_json: &T3Json, path: &Path) -> eyre::Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+    if temp_path.exists() {
+        std::fs::remove_file(&temp_path)
+            .wrap_err_with(|| format!("Failed to remove stale {:?}", temp_path))?;
+    }
+    let written = Connection::open(&temp_path)
+        .map_err(eyre::Report::from)
+        .and_then(|mut connection| {
+            insert(&mut connection, &[t3_json])?;
+            connection.close().map_err(|(_, e)| e)?;
+            Ok(())
+        })
+        .and_then(|()| {
+            std::fs::rename(&temp_path, path)
+                .wrap_err_with(|| format!("Failed to replace {:?}", path))
+        });
+    if written.is_err() {
+        std::fs::remove_file(&temp_path).ok();
+    }
+    written?;
+    info!(
+        "Wrote {} threads and {} messages to {:?}",
+        t3_json.threads.len(),
+        t3_json.messages.len(),
+        path
+    );
+    Ok(())
+}
+
+/// Creates the tables in an empty database and fills them with the exports.
+/// Later rows replace earlier ones with the same id.
+pub fn insert(connection: &mut Connection, exports: &[&T3Json]) -> eyre::Result<()> {
+    let transaction = connection.transaction()?;
+    transaction.execute_batch(SCHEMA)?;
+    {
+        let mut insert_thread = transaction
+            .prepare("INSERT OR REPLACE INTO threads VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)")?;
+        for thread in exports.iter().flat_map(|t3_json| &t3_json.threads) {
+            insert_thread.execute(params![
+                thread.id,
+                thread.title,
+                thread.user_edited_title,
+                thread.status.as_str(),
+                thread.model,
+                thread.created_at.timestamp_millis(),
+                thread.updated_at.map(|t| t.timestamp_millis()),
+                thread.last_message_at.timestamp_millis(),
+            ])?;
+        }
+
+        let mut insert_message = transaction.prepare(
+            "INSERT OR REPLACE INTO messages VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        )?;
+        for message in exports.iter().flat_map(|t3_json| &t3_json.messages) {
+            insert_message.execute(params![
+                message.id,
+                message.thread_id,
+                message.role.as_str(),
+                message.status.as_str(),
+                message.model,
+                message.created_at.timestamp_millis(),
+                message.content,
+                message
+                    .model_params
+                    .as_ref()
+                    .map(serde_json::to_string)
+                    .transpose()?,
+                message
+                    .attachments
+                    .as_ref()
+                    .map(serde_json::to_string)
+                    .transpose()?,
+            ])?;
+        }
+    }
+    transaction.execute(
+        "INSERT INTO messages_fts (messages_fts) VALUES ('rebuild')",
+        [],
+    )?;
+    transaction.commit()?;
+    Ok(())
+}
+
+/// Reads an export back from a database written by `write`.
+pub fn read(path: &Path) -> eyre::Result<T3Json> {
+    let context = || format!("Failed to read T3Json from SQLite database {:?}", path);
+    let connection = Connection::open(path).wrap_err_with(context)?;
+    let threads = connection
+        .prepare(
+            "SELECT id, title, user_edited_title, status, model, created_at, updated_at,
+                last_message_at
+            FROM threads ORDER BY rowid",
+        )
+        .and_then(|mut statement| {
+            statement
+                .query_map([], |row| {
+                    Ok(T3Thread {
+                        id: row.get(0)?,
+                        title: row.get(1)?,
+                        user_edited_title: row.get(2)?,
+                        status: get_enum(row, 3)?,
+                        model: row.get(4)?,
+                        created_at: get_timestamp(row, 5)?,
+                        updated_at: match row.get_ref(6)? {
+                            ValueRef::Null => None,
+                            _ => Some(get_timestamp(row, 6)?),
+                        },
+                        last_message_at: get_timestamp(row, 7)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<T3Thread>>>()
+        })
+        .wrap_err_with(context)?;
+
+    let messages = connection
+        .prepare(
+            "SELECT id, thread_id, role, status, model, created_at, content, model_params,
+                attachments
+            FROM messages ORDER BY rowid",
+        )
+        .and_then(|mut statement| {
+            statement
+                .query_map([], |row| {
+                    Ok(T3Message {
+                        id: row.get(0)?,
+                        thread_id: row.get(1)?,
+                        role: get_enum(row, 2)?,
+                        status: get_enum(row, 3)?,
+                        model: row.get(4)?,
+                        created_at: get_timestamp(row, 5)?,
+                        content: row.get(6)?,
+                        model_params: get_json(row, 7)?,
+                        attachments: get_json(row, 8)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<T3Message>>>()
+        })
+        .wrap_err_with(context)?;
+
+    info!(
+        "Read {} threads and {} messages from {:?}",
+        threads.len(),
+        messages.len(),
+        path
+    );
+    Ok(T3Json { threads, messages })
+}
+
+/// Reads a column holding an enum's `as_str` name.
+fn get_enum<T: DeserializeOwned>(row: &Row, index: usize) -> rusqlite::Result<T> {
+    let name: String = row.get(index)?;
+    serde_json::from_value(serde_json::Value::String(name))
+        .map_err(|e| FromSqlConversionFailure(index, Type::Text, Box::new(e)))
+}
+
+/// Reads a nullable column holding JSON text.
+fn get_json<T: DeserializeOwned>(row: &Row, index: usize) -> rusqlite::Result<Option<T>> {
+    let Some(json): Option<String> = row.get(index)? else {
+        return Ok(None);
+    };
+    serde_json::from_str(&json)
+        .map(Some)
+        .map_err(|e| FromSqlConversionFailure(index, Type::Text, Box::new(e)))
+}
+
+/// Reads a column holding milliseconds since the epoch.
+fn get_timestamp(row: &Row, index: usize) -> rusqlite::Result<T3Timestamp> {
+    let millis: i64 = row.get(index)?;
+    Utc.timestamp_millis_opt(millis)
+        .single()
+        .map(T3Timestamp::millis)
+        .ok_or_else(|| {
+            FromSqlConversionFailure(
+                index,
+                Type::Integer,
+                format!("invalid timestamp: {}", millis).into(),
+            )
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::t3_json::T3Json;
+    use crate::t3_json::fixture;
+    use crate::t3_timestamp::TimestampForm;
+    use crate::t3_timestamp::serialize_as;
+
+    #[test]
+    fn round_trips_and_indexes_content() -> eyre::Result<()> {
+        let t3_json = fixture::export(
+            [fixture::thread("t1")
+                .title("Rust lifetimes")
+                .set("created_at", "2025-01-01T00:00:00Z")],
+            [fixture::message("a", "t1")
+                .content("What does the borrow checker do?")
+                .role("assistant")
+                .status("cancelled")
+                .set(
+                    "modelParams",
+                    serde_json::json!({ "reasoningEffort": "high" }),
+                )],
+        );
+        let dir = std::env::temp_dir().join(format!("t3-sqlite-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join("export.sqlite");
+        // Replaces an existing file without leaving the temporary database behind
+        std::fs::write(&path, "not a database")?;
+        super::write(&t3_json, &path)?;
+        assert!(!dir.join("export.sqlite.tmp").exists());
+
+        let connection = rusqlite::Connection::open(&path)?;
+        let matched: String = connection.query_row(
+            "SELECT m.thread_id FROM messages_fts JOIN messages m ON m.rowid = messages_fts.rowid
+            WHERE messages_fts MATCH 'borrow'",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(matched, "t1");
+        drop(connection);
+
+        let read = super::read(&path)?;
+        std::fs::remove_dir_all(&dir)?;
+        // The database keeps milliseconds only, so compare with every timestamp in that form
+        let as_millis = |t3_json: &T3Json| {
+            serialize_as(Some(TimestampForm::Millis), || {
+                serde_json::to_value(t3_json)
+            })
+        };
+        assert_eq!(as_millis(&read)?, as_millis(&t3_json)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,401 @@
+// src/stats.rs
+
+use eframe::egui;
+use egui_extras::Column;
+use egui_extras::TableBuilder;
+use egui_plot::Legend;
+use egui_plot::Line;
+use egui_plot::Plot;
+use egui_plot::PlotPoints;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use crate::cost::Tokenizer;
+use crate::cost::TokenizerKind;
+use crate::filter::DrillDown;
+use crate::filter::FilteredView;
+use crate::filter::ThreadFilter;
+use crate::t3_json::T3Message;
+
+/// Number of bins in the length distribution plot.
+const HISTOGRAM_BINS: usize = 40;
+/// Number of outlier threads listed per statistic.
+const OUTLIER_COUNT: usize = 5;
+
+/// Returns the value at quantile `q` (0.0..=1.0) of an ascending slice, using the nearest rank.
+pub fn quantile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (q * (sorted.len() - 1) as f64).round() as usize;
+    sorted[rank.min(sorted.len() - 1)]
+}
+
+/// Formats a duration in seconds using the two most significant units.
+pub fn format_seconds(seconds: f64) -> String {
+    if seconds < 60.0 {
+        return format!("{:.1}s", seconds);
+    }
+    let total = seconds.round() as i64;
+    if total < 3600 {
+        format!("{}m {:02}s", total / 60, total % 60)
+    } else if total < 86400 {
+        format!("{}h {:02}m", total / 3600, total % 3600 / 60)
+    } else {
+        format!("{}d {:02}h", total / 86400, total % 86400 / 3600)
+    }
+}
+
+/// Summary numbers for a set of values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub count: usize,
+    pub median: f64,
+    pub p90: f64,
+    pub max: f64,
+}
+
+impl Summary {
+    /// Summarises the values. The slice is sorted in place.
+    pub fn of(values: &mut [f64]) -> Self {
+        values.sort_by(f64::total_cmp);
+        Self {
+            count: values.len(),
+            median: quantile(values, 0.5),
+            p90: quantile(values, 0.9),
+            max: values.last().copied().unwrap_or(0.0),
+        }
+    }
+}
+
+/// How a message's length is measured.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum LengthMetric {
+    #[default]
+    Chars,
+    Words,
+    Tokens,
+}
+
+impl LengthMetric {
+    /// Returns a human-readable name for the metric.
+    pub fn name(&self) -> &'static str {
+        match self {
+            LengthMetric::Chars => "Characters",
+            LengthMetric::Words => "Words",
+            LengthMetric::Tokens => "Estimated tokens",
+        }
+    }
+
+    /// Returns all available metrics.
+    pub fn all() -> &'static [LengthMetric] {
+        &[
+            LengthMetric::Chars,
+            LengthMetric::Words,
+            LengthMetric::Tokens,
+        ]
+    }
+
+    /// Measures the content of a message. Tokens are estimated with the given tokenizer.
+    pub fn measure(&self, message: &T3Message, tokenizer: &dyn Tokenizer) -> f64 {
+        match self {
+            LengthMetric::Chars => message.content.chars().count() as f64,
+            LengthMetric::Words => message.content.split_whitespace().count() as f64,
+            LengthMetric::Tokens => tokenizer.estimate(&message.content) as f64,
+        }
+    }
+}
+
+/// What message length distributions are split by.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum StatsGrouping {
+    #[default]
+    Role,
+    Model,
+}
+
+impl StatsGrouping {
+    fn key(&self, message: &T3Message) -> String {
+        match self {
+            StatsGrouping::Role => format!("{:?}", message.role),
+            StatsGrouping::Model => message.model.clone(),
+        }
+    }
+}
+
+/// A thread whose length or duration stands out.
+struct Outlier {
+    thread_id: String,
+    title: String,
+    value: f64,
+}
+
+/// Everything shown by the statistics view, computed once per filter and settings.
+struct ComputedStats {
+    /// Length summary and histogram line per group, ordered by group name.
+    groups: Vec<(String, Summary, Vec<[f64; 2]>)>,
+    thread_lengths: Summary,
+    longest_threads: Vec<Outlier>,
+    thread_durations: Summary,
+    longest_durations: Vec<Outlier>,
+}
+
+/// State for the statistics view of a file.
+#[derive(Default)]
+pub struct StatsState {
+    pub metric: LengthMetric,
+    pub grouping: StatsGrouping,
+    computed: Option<(
+        (ThreadFilter, LengthMetric, StatsGrouping, TokenizerKind),
+        ComputedStats,
+    )>,
+}
+
+impl StatsState {
+    fn compute(&self, view: &FilteredView, tokenizer: TokenizerKind) -> ComputedStats {
+        // Message lengths per group
+        let tokenizer = tokenizer.tokenizer();
+        let mut lengths: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+        for message in &view.messages {
+            lengths
+                .entry(self.grouping.key(message))
+                .or_default()
+                .push(self.metric.measure(message, tokenizer.as_ref()));
+        }
+        let mut all: Vec<f64> = lengths.values().flatten().copied().collect();
+        // Clip the histogram at p99 so a handful of huge pastes don't squash everything else
+        all.sort_by(f64::total_cmp);
+        let upper = quantile(&all, 0.99).max(1.0);
+        let bin_width = upper / HISTOGRAM_BINS as f64;
+        let groups = lengths
+            .into_iter()
+            .map(|(group, mut values)| {
+                let summary = Summary::of(&mut values);
+                let mut bins = [0usize; HISTOGRAM_BINS];
+                for value in values.iter().filter(|value| **value <= upper) {
+                    let bin = ((value / bin_width) as usize).min(HISTOGRAM_BINS - 1);
+                    bins[bin] += 1;
+                }
+                let points = bins
+                    .iter()
+                    .enumerate()
+                    .map(|(bin, count)| [(bin as f64 + 0.5) * bin_width, *count as f64])
+                    .collect();
+                (group, summary, points)
+            })
+            .collect();
+
+        // Thread lengths and durations
+        let mut message_counts: HashMap<&str, usize> = HashMap::new();
+        for message in &view.messages {
+            *message_counts
+                .entry(message.thread_id.as_str())
+                .or_insert(0) += 1;
+        }
+        let mut by_length: Vec<Outlier> = view
+            .threads
+            .iter()
+            .map(|thread| Outlier {
+                thread_id: thread.id.clone(),
+                title: thread.title.clone(),
+                value: *message_counts.get(thread.id.as_str()).unwrap_or(&0) as f64,
+            })
+            .collect();
+        let mut by_duration: Vec<Outlier> = view
+            .threads
+            .iter()
+            .map(|thread| Outlier {
+                thread_id: thread.id.clone(),
+                title: thread.title.clone(),
+                value: (*thread.last_message_at - *thread.created_at).num_seconds() as f64,
+            })
+            .collect();
+        let thread_lengths =
+            Summary::of(&mut by_length.iter().map(|o| o.value).collect::<Vec<_>>());
+        let thread_durations =
+            Summary::of(&mut by_duration.iter().map(|o| o.value).collect::<Vec<_>>());
+        by_length.sort_by(|a, b| b.value.total_cmp(&a.value));
+        by_length.truncate(OUTLIER_COUNT);
+        by_duration.sort_by(|a, b| b.value.total_cmp(&a.value));
+        by_duration.truncate(OUTLIER_COUNT);
+
+        ComputedStats {
+            groups,
+            thread_lengths,
+            longest_threads: by_length,
+            thread_durations,
+            longest_durations: by_duration,
+        }
+    }
+
+    /// Draws length distributions and thread statistics for the filtered view.
+    /// Clicking an outlier thread returns a drill-down to it.
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        view: &FilteredView,
+        filter: &ThreadFilter,
+        tokenizer: TokenizerKind,
+    ) -> Option<DrillDown> {
+        ui.horizontal(|ui| {
+            ui.label("Message length in:");
+            egui::ComboBox::from_id_salt("stats_metric")
+                .selected_text(self.metric.name())
+                .show_ui(ui, |ui| {
+                    for metric in LengthMetric::all() {
+                        ui.selectable_value(&mut self.metric, *metric, metric.name());
+                    }
+                });
+            ui.label("by");
+            ui.selectable_value(&mut self.grouping, StatsGrouping::Role, "Role");
+            ui.selectable_value(&mut self.grouping, StatsGrouping::Model, "Model");
+        });
+
+        let key = (filter.clone(), self.metric, self.grouping, tokenizer);
+        if self
+            .computed
+            .as_ref()
+            .is_none_or(|(cached, _)| *cached != key)
+        {
+            self.computed = Some((key, self.compute(view, tokenizer)));
+        }
+        let Some((_, stats)) = &self.computed else {
+            return None;
+        };
+
+        TableBuilder::new(ui)
+            .id_salt("message_length_summary")
+            .striped(true)
+            .column(Column::auto().at_least(120.0).clip(true))
+            .columns(Column::auto().at_least(60.0), 4)
+            .header(20.0, |mut header| {
+                for title in ["Group", "Messages", "Median", "P90", "Max"] {
+                    header.col(|ui| {
+                        ui.strong(title);
+                    });
+                }
+            })
+            .body(|mut body| {
+                for (group, summary, _) in &stats.groups {
+                    body.row(18.0, |mut row| {
+                        row.col(|ui| {
+                            ui.label(group);
+                        });
+                        for value in [
+                            summary.count as f64,
+                            summary.median,
+                            summary.p90,
+                            summary.max,
+                        ] {
+                            row.col(|ui| {
+                                ui.monospace(format!("{}", value));
+                            });
+                        }
+                    });
+                }
+            });
+
+        Plot::new("message_length_distribution")
+            .height(200.0)
+            .legend(Legend::default())
+            .x_axis_label(self.metric.name())
+            .y_axis_label("Messages")
+            .show(ui, |plot_ui| {
+                for (group, _, points) in &stats.groups {
+                    plot_ui.line(Line::new(group, PlotPoints::from(points.clone())));
+                }
+            });
+
+        let mut drill_down = None;
+        ui.separator();
+        ui.label(format!(
+            "Thread length: median {} messages, p90 {}, max {}",
+            stats.thread_lengths.median, stats.thread_lengths.p90, stats.thread_lengths.max
+        ));
+        draw_outliers(ui, &stats.longest_threads, &mut drill_down, |value| {
+            format!("{} messages", value)
+        });
+        ui.separator();
+        ui.label(format!(
+            "Thread duration: median {}, p90 {}, max {}",
+            format_seconds(stats.thread_durations.median),
+            format_seconds(stats.thread_durations.p90),
+            format_seconds(stats.thread_durations.max),
+        ));
+        draw_outliers(
+            ui,
+            &stats.longest_durations,
+            &mut drill_down,
+            format_seconds,
+        );
+        drill_down
+    }
+}
+
+fn draw_outliers(
+    ui: &mut egui::Ui,
+    outliers: &[Outlier],
+    drill_down: &mut Option<DrillDown>,
+    format_value: impl Fn(f64) -> String,
+) {
+    for outlier in outliers {
+        ui.horizontal(|ui| {
+            ui.monospace(format_value(outlier.value));
+            if ui.link(&outlier.title).clicked() {
+                *drill_down = Some(DrillDown::Thread {
+                    id: outlier.thread_id.clone(),
+                    title: outlier.title.clone(),
+                });
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LengthMetric;
+    use super::Summary;
+    use super::format_seconds;
+    use super::quantile;
+    use crate::cost::CharsPerToken;
+    use crate::cost::TokensPerWord;
+    use crate::t3_json::fixture;
+
+    #[test]
+    fn quantiles_use_the_nearest_rank() {
+        assert_eq!(quantile(&[], 0.5), 0.0);
+        assert_eq!(quantile(&[7.0], 0.0), 7.0);
+        assert_eq!(quantile(&[7.0], 0.9), 7.0);
+        let values: Vec<f64> = (1..=11).map(f64::from).collect();
+        assert_eq!(quantile(&values, 0.5), 6.0);
+        assert_eq!(quantile(&values, 0.9), 10.0);
+        assert_eq!(quantile(&values, 1.0), 11.0);
+
+        let mut unsorted = [3.0, 1.0, 2.0];
+        let summary = Summary::of(&mut unsorted);
+        assert_eq!((summary.count, summary.median, summary.max), (3, 2.0, 3.0));
+    }
+
+    #[test]
+    fn formats_two_most_significant_units() {
+        assert_eq!(format_seconds(4.25), "4.2s");
+        assert_eq!(format_seconds(125.0), "2m 05s");
+        assert_eq!(format_seconds(3_660.0), "1h 01m");
+        assert_eq!(format_seconds(90_000.0), "1d 01h");
+    }
+
+    #[test]
+    fn measures_chars_words_and_tokens() {
+        let message = fixture::message("a", "t1")
+            .content("héllo  big worlds")
+            .build();
+        let chars = CharsPerToken::default();
+        assert_eq!(LengthMetric::Chars.measure(&message, &chars), 17.0);
+        assert_eq!(LengthMetric::Words.measure(&message, &chars), 3.0);
+        // Four characters per token, rounded up
+        assert_eq!(LengthMetric::Tokens.measure(&message, &chars), 5.0);
+        // Four tokens per three words, rounded up
+        let words = TokensPerWord::default();
+        assert_eq!(LengthMetric::Tokens.measure(&message, &words), 4.0);
+    }
+}
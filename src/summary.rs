@@ -0,0 +1,308 @@
+// src/summary.rs
+
+use chrono::Duration;
+use chrono::NaiveDate;
+use eframe::egui;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use crate::filter::DrillDown;
+use crate::t3_json::T3Json;
+use crate::t3_json::T3MessageRole;
+use crate::time_bucket::DateRange;
+use crate::timezone::DisplayTimezone;
+
+/// Number of models listed in the overview.
+const TOP_MODELS: usize = 3;
+
+/// A run of consecutive days with at least one message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Streak {
+    pub start: NaiveDate,
+    pub days: usize,
+}
+
+/// The thread with the most messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopThread {
+    pub id: String,
+    pub title: String,
+    pub messages: usize,
+}
+
+/// Headline numbers for a whole export, shown when it is loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageSummary {
+    /// First and last day with a message.
+    pub span: Option<DateRange>,
+    pub threads: usize,
+    pub messages: usize,
+    pub active_days: usize,
+    pub longest_streak: Option<Streak>,
+    pub busiest_day: Option<(NaiveDate, usize)>,
+    /// Models with the most responses, most used first.
+    pub top_models: Vec<(String, usize)>,
+    pub top_thread: Option<TopThread>,
+    pub average_messages_per_thread: f64,
+}
+
+impl UsageSummary {
+    /// Summarises the export, counting days in the given timezone.
+    pub fn new(t3_json: &T3Json, timezone: DisplayTimezone) -> Self {
+        let mut per_day: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+        let mut per_model: HashMap<&str, usize> = HashMap::new();
+        let mut per_thread: HashMap<&str, usize> = HashMap::new();
+        for message in &t3_json.messages {
+            *per_day
+                .entry(timezone.naive(*message.created_at).date())
+                .or_default() += 1;
+            *per_thread.entry(message.thread_id.as_str()).or_default() += 1;
+            // User messages carry the model they were sent to; count each response once
+            if matches!(message.role, T3MessageRole::Assistant) {
+                *per_model.entry(message.model.as_str()).or_default() += 1;
+            }
+        }
+
+        let span = match (per_day.keys().next(), per_day.keys().next_back()) {
+            (Some(start), Some(end)) => Some(DateRange {
+                start: *start,
+                end: *end,
+            }),
+            _ => None,
+        };
+
+        let mut longest_streak: Option<Streak> = None;
+        let mut current: Option<Streak> = None;
+        for day in per_day.keys() {
+            let streak = match current {
+                Some(streak) if streak.start + Duration::days(streak.days as i64) == *day => {
+                    Streak {
+                        days: streak.days + 1,
+                        ..streak
+                    }
+                }
+                _ => Streak {
+                    start: *day,
+                    days: 1,
+                },
+            };
+            if longest_streak.is_none_or(|longest| streak.days > longest.days) {
+                longest_streak = Some(streak);
+            }
+            current = Some(streak);
+        }
+
+        // Ties go to the earliest day
+        let mut busiest_day: Option<(NaiveDate, usize)> = None;
+        for (day, count) in &per_day {
+            if busiest_day.is_none_or(|(_, most)| *count > most) {
+                busiest_day = Some((*day, *count));
+            }
+        }
+
+        let mut top_models: Vec<(String, usize)> = per_model
+            .into_iter()
+            .map(|(model, count)| (model.to_string(), count))
+            .collect();
+        top_models.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+        top_models.truncate(TOP_MODELS);
+
+        let top_thread = t3_json
+            .threads
+            .iter()
+            .filter_map(|thread| {
+                let messages = per_thread.get(thread.id.as_str()).copied()?;
+                Some(TopThread {
+                    id: thread.id.clone(),
+                    title: thread.title.clone(),
+                    messages,
+                })
+            })
+            .max_by(|a, b| a.messages.cmp(&b.messages).then(b.id.cmp(&a.id)));
+
+        let threads = t3_json.threads.len();
+        let messages = t3_json.messages.len();
+        Self {
+            span,
+            threads,
+            messages,
+            active_days: per_day.len(),
+            longest_streak,
+            busiest_day,
+            top_models,
+            top_thread,
+            average_messages_per_thread: if threads == 0 {
+                0.0
+            } else {
+                messages as f64 / threads as f64
+            },
+        }
+    }
+}
+
+/// State for the overview at the top of a file's window.
+#[derive(Default)]
+pub struct SummaryState {
+    computed: Option<(DisplayTimezone, UsageSummary)>,
+}
+
+impl SummaryState {
+    /// Draws the overview of the whole export, ignoring filters. Clicking the busiest day,
+    /// a model or the top thread returns a drill-down to it.
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        t3_json: &T3Json,
+        timezone: DisplayTimezone,
+    ) -> Option<DrillDown> {
+        if self
+            .computed
+            .as_ref()
+            .is_none_or(|(cached, _)| *cached != timezone)
+        {
+            self.computed = Some((timezone, UsageSummary::new(t3_json, timezone)));
+        }
+        let Some((_, summary)) = &self.computed else {
+            return None;
+        };
+
+        let mut drill_down = None;
+        egui::Grid::new("usage_summary")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Span:");
+                match &summary.span {
+                    Some(span) => ui.monospace(format!(
+                        "{} to {} ({} days)",
+                        span.start.format("%Y-%m-%d"),
+                        span.end.format("%Y-%m-%d"),
+                        (span.end - span.start).num_days() + 1
+                    )),
+                    None => ui.label("No messages"),
+                };
+                ui.end_row();
+
+                ui.label("Threads:");
+                ui.monospace(summary.threads.to_string());
+                ui.end_row();
+
+                ui.label("Messages:");
+                ui.monospace(format!(
+                    "{} ({:.1} per thread)",
+                    summary.messages, summary.average_messages_per_thread
+                ));
+                ui.end_row();
+
+                ui.label("Active days:");
+                ui.monospace(summary.active_days.to_string());
+                ui.end_row();
+
+                if let Some(streak) = summary.longest_streak {
+                    ui.label("Longest streak:");
+                    ui.monospace(format!(
+                        "{} days from {}",
+                        streak.days,
+                        streak.start.format("%Y-%m-%d")
+                    ));
+                    ui.end_row();
+                }
+
+                if let Some((day, count)) = summary.busiest_day {
+                    ui.label("Busiest day:");
+                    if ui
+                        .link(format!("{} ({} messages)", day.format("%Y-%m-%d"), count))
+                        .clicked()
+                    {
+                        drill_down = Some(DrillDown::Day(day));
+                    }
+                    ui.end_row();
+                }
+
+                if let (Some(span), false) = (summary.span, summary.top_models.is_empty()) {
+                    ui.label("Top models:");
+                    ui.horizontal_wrapped(|ui| {
+                        for (model, count) in &summary.top_models {
+                            if ui.link(format!("{} ({})", model, count)).clicked() {
+                                drill_down = Some(DrillDown::Model {
+                                    model: model.clone(),
+                                    range: span,
+                                });
+                            }
+                        }
+                    });
+                    ui.end_row();
+                }
+
+                if let Some(thread) = &summary.top_thread {
+                    ui.label("Top thread:");
+                    if ui
+                        .link(format!("{} ({} messages)", thread.title, thread.messages))
+                        .clicked()
+                    {
+                        drill_down = Some(DrillDown::Thread {
+                            id: thread.id.clone(),
+                            title: thread.title.clone(),
+                        });
+                    }
+                    ui.end_row();
+                }
+            });
+        drill_down
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Streak;
+    use super::UsageSummary;
+    use crate::t3_json::fixture;
+    use crate::timezone::DisplayTimezone;
+    use chrono::NaiveDate;
+
+    fn message(
+        id: &str,
+        thread_id: &str,
+        role: &str,
+        model: &str,
+        day: u32,
+    ) -> fixture::MessageBuilder {
+        fixture::message(id, thread_id)
+            .role(role)
+            .model(model)
+            .created_at(format!("2025-01-{:02}T12:00:00Z", day))
+    }
+
+    #[test]
+    fn finds_streaks_and_busiest_day() {
+        let t3_json = fixture::export(
+            [fixture::thread("t1"), fixture::thread("t2")],
+            [
+                message("a", "t1", "user", "m1", 1),
+                message("b", "t1", "assistant", "m1", 2),
+                message("c", "t2", "user", "m2", 5),
+                message("d", "t2", "assistant", "m2", 6),
+                message("e", "t2", "assistant", "m2", 6),
+                message("f", "t2", "assistant", "m2", 7),
+            ],
+        );
+        let summary = UsageSummary::new(&t3_json, DisplayTimezone::Named(chrono_tz::UTC));
+        let day = |day| NaiveDate::from_ymd_opt(2025, 1, day).unwrap();
+        assert_eq!(summary.active_days, 5);
+        assert_eq!(
+            summary.longest_streak,
+            Some(Streak {
+                start: day(5),
+                days: 3
+            })
+        );
+        assert_eq!(summary.busiest_day, Some((day(6), 2)));
+        assert_eq!(summary.span.map(|span| span.end), Some(day(7)));
+        assert_eq!(summary.top_models[0], ("m2".to_string(), 3));
+        assert_eq!(
+            summary.top_thread.map(|thread| thread.id),
+            Some("t2".to_string())
+        );
+        assert_eq!(summary.average_messages_per_thread, 3.0);
+    }
+}
@@ -1,17 +1,21 @@
+use crate::attachment::T3Attachment;
+use crate::dump;
+use crate::model_params::T3ModelParams;
+use crate::sqlite;
 use crate::t3_timestamp::T3Timestamp;
 use eframe::egui::DroppedFile;
 use eyre::bail;
 use serde::Deserialize;
 use serde::Serialize;
-use serde_json::Value;
+use std::path::Path;
 use tracing::info;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct T3Json {
     pub threads: Vec<T3Thread>,
     pub messages: Vec<T3Message>,
 }
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct T3Thread {
     pub title: String,
     pub user_edited_title: bool,
@@ -23,14 +27,14 @@ pub struct T3Thread {
     pub last_message_at: T3Timestamp,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum T3ThreadStatus {
     Done,
     Completed,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct T3Message {
     pub id: String,
     #[serde(rename = "threadId")]
@@ -41,18 +45,18 @@ pub struct T3Message {
     pub status: T3MessageStatus,
     pub model: String,
     #[serde(rename = "modelParams")]
-    pub model_params: Option<Value>,
-    pub attachments: Option<Vec<Value>>,
+    pub model_params: Option<T3ModelParams>,
+    pub attachments: Option<Vec<T3Attachment>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum T3MessageRole {
     User,
     Assistant,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum T3MessageStatus {
     Done,
@@ -64,6 +68,41 @@ pub enum T3MessageStatus {
     Thinking,
 }
 
+impl T3ThreadStatus {
+    /// Returns the name used in exports, e.g. `"done"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            T3ThreadStatus::Done => "done",
+            T3ThreadStatus::Completed => "completed",
+        }
+    }
+}
+
+impl T3MessageRole {
+    /// Returns the name used in exports, e.g. `"assistant"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            T3MessageRole::User => "user",
+            T3MessageRole::Assistant => "assistant",
+        }
+    }
+}
+
+impl T3MessageStatus {
+    /// Returns the name used in exports, e.g. `"cancelled"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            T3MessageStatus::Done => "done",
+            T3MessageStatus::Deleted => "deleted",
+            T3MessageStatus::Error => "error",
+            T3MessageStatus::Cancelled => "cancelled",
+            T3MessageStatus::Waiting => "waiting",
+            T3MessageStatus::Streaming => "streaming",
+            T3MessageStatus::Thinking => "thinking",
+        }
+    }
+}
+
 impl T3Json {
     pub async fn try_from_async(dropped_file: DroppedFile) -> eyre::Result<Self> {
         info!(
@@ -74,7 +113,7 @@ impl T3Json {
         let bytes = match dropped_file.bytes {
             Some(ref bytes) => bytes.as_ref(),
             None => {
-                let Some(path) = dropped_file.path else {
+                let Some(path) = &dropped_file.path else {
                     bail!("Dropped file has no bytes or path");
                 };
                 bytes = tokio::fs::read(path).await?;
@@ -83,11 +122,145 @@ impl T3Json {
             }
         };
 
-        let t3_json: T3Json = serde_json::from_slice(bytes)
+        if bytes.starts_with(sqlite::MAGIC) {
+            let Some(path) = dropped_file.path.clone() else {
+                bail!("SQLite databases can only be loaded from a file path");
+            };
+            return tokio::task::spawn_blocking(move || sqlite::read(&path)).await?;
+        }
+
+        let t3_json = dump::parse(bytes)
             .map_err(|e| eyre::eyre!("Failed to parse JSON string to T3Json: {:#?}", e))?;
         info!("Parsed T3Json: {:?}", t3_json.threads.len());
         Ok(t3_json)
     }
+
+    pub fn read(path: &Path) -> eyre::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        if bytes.starts_with(sqlite::MAGIC) {
+            return sqlite::read(path);
+        }
+        dump::parse(&bytes).map_err(|e| {
+            eyre::eyre!(
+                "Failed to parse JSON string to T3Json from {:?}: {:#?}",
+                path,
+                e
+            )
+        })
+    }
+}
+
+/// Builders for exports in tests. Each starts from a plain message or thread that tests
+/// override only where it matters to them.
+#[cfg(test)]
+pub(crate) mod fixture {
+    use super::T3Json;
+    use super::T3Message;
+    use serde_json::Value;
+    use serde_json::json;
+
+    /// 2025-01-01T00:00:00Z in milliseconds, the default for every timestamp.
+    /// Offsets are added to it, since small values read as seconds.
+    pub const START: i64 = 1_735_689_600_000;
+
+    /// A message as it appears in an export.
+    pub struct MessageBuilder(Value);
+
+    /// Starts an empty, finished user message to "m1" sent at `START`.
+    pub fn message(id: &str, thread_id: &str) -> MessageBuilder {
+        MessageBuilder(json!({
+            "id": id,
+            "threadId": thread_id,
+            "content": "",
+            "created_at": START,
+            "role": "user",
+            "status": "done",
+            "model": "m1",
+            "modelParams": null,
+            "attachments": null,
+        }))
+    }
+
+    impl MessageBuilder {
+        pub fn role(self, role: &str) -> Self {
+            self.set("role", role)
+        }
+
+        pub fn status(self, status: &str) -> Self {
+            self.set("status", status)
+        }
+
+        pub fn model(self, model: &str) -> Self {
+            self.set("model", model)
+        }
+
+        pub fn content(self, content: &str) -> Self {
+            self.set("content", content)
+        }
+
+        pub fn created_at(self, created_at: impl Into<Value>) -> Self {
+            self.set("created_at", created_at)
+        }
+
+        /// Sets any other field, e.g. `modelParams`.
+        pub fn set(mut self, key: &str, value: impl Into<Value>) -> Self {
+            self.0[key] = value.into();
+            self
+        }
+
+        pub fn json(self) -> Value {
+            self.0
+        }
+
+        pub fn build(self) -> T3Message {
+            serde_json::from_value(self.0).unwrap()
+        }
+    }
+
+    /// A thread as it appears in an export.
+    pub struct ThreadBuilder(Value);
+
+    /// Starts a finished thread titled with its id, created and last active at `START`.
+    pub fn thread(id: &str) -> ThreadBuilder {
+        ThreadBuilder(json!({
+            "id": id,
+            "title": id,
+            "user_edited_title": false,
+            "status": "done",
+            "model": "m1",
+            "created_at": START,
+            "updated_at": null,
+            "last_message_at": START,
+        }))
+    }
+
+    impl ThreadBuilder {
+        pub fn title(self, title: &str) -> Self {
+            self.set("title", title)
+        }
+
+        /// Sets any other field, e.g. `updated_at`.
+        pub fn set(mut self, key: &str, value: impl Into<Value>) -> Self {
+            self.0[key] = value.into();
+            self
+        }
+
+        pub fn json(self) -> Value {
+            self.0
+        }
+    }
+
+    /// Builds an export from the given threads and messages.
+    pub fn export(
+        threads: impl IntoIterator<Item = ThreadBuilder>,
+        messages: impl IntoIterator<Item = MessageBuilder>,
+    ) -> T3Json {
+        serde_json::from_value(json!({
+            "threads": threads.into_iter().map(ThreadBuilder::json).collect::<Vec<_>>(),
+            "messages": messages.into_iter().map(MessageBuilder::json).collect::<Vec<_>>(),
+        }))
+        .unwrap()
+    }
 }
 
 #[cfg(test)]
@@ -99,12 +272,7 @@ mod test {
         let t3_backup_path = Path::new(r#"C:\Users\TeamD\OneDrive\Documents\Backups\t3chat"#);
         let newest_file_in_backup_path = std::fs::read_dir(t3_backup_path)?
             .filter_map(|entry| entry.ok())
-            .filter(|entry| {
-                entry
-                    .path()
-                    .extension()
-                    .map_or(false, |ext| ext == "json")
-            })
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
             .max_by_key(|entry| entry.metadata().and_then(|m| m.modified()).ok())
             .map(|entry| entry.path())
             .ok_or_else(|| eyre::eyre!("No JSON files found in backup path"))?;
@@ -123,7 +291,7 @@ mod test {
         for entry in std::fs::read_dir(t3_backup_path)? {
             let entry = entry?;
             let path = entry.path();
-            if path.extension().map_or(false, |ext| ext == "json") {
+            if path.extension().is_some_and(|ext| ext == "json") {
                 let file_bytes = std::fs::read(&path)?;
                 match serde_json::from_slice::<super::T3Json>(&file_bytes) {
                     Ok(t3_json) => {
@@ -147,4 +315,4 @@ mod test {
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
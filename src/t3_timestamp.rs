@@ -1,10 +1,133 @@
-use chrono::{DateTime, TimeZone, Utc};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone, Utc};
 use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::Cell;
+use std::cmp::Ordering;
 use std::fmt;
 use std::ops::Deref;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub struct T3Timestamp(pub DateTime<Utc>);
+/// A point in time, remembering how it was written so it serializes back the same way.
+///
+/// Comparisons only look at the point in time.
+#[derive(Debug, Clone, Copy)]
+pub struct T3Timestamp(pub DateTime<Utc>, pub TimestampForm);
+
+/// The timestamp forms accepted when parsing, for error messages.
+const ACCEPTED_FORMS: &str = "epoch milliseconds or seconds as an integer or float \
+    (e.g. 1738324800000 or 1738324800.5), an RFC 3339 string (e.g. 2025-01-31T12:00:00Z) \
+    or an ISO 8601 date-time without timezone, read as UTC (e.g. 2025-01-31T12:00:00)";
+
+/// Epoch values with a smaller magnitude are seconds, larger ones milliseconds.
+/// 10^11 seconds is in the year 5138 and 10^11 milliseconds in March 1973, so real dates of
+/// either kind land on the right side.
+const SECONDS_LIMIT: f64 = 1e11;
+
+/// `strftime` formats of ISO 8601 date-times without timezone, tried in order.
+const NAIVE_FORMATS: [&str; 2] = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"];
+
+/// How a timestamp is written in JSON.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimestampForm {
+    /// Integer milliseconds since the epoch, as t3.chat exports them.
+    #[default]
+    Millis,
+    /// Integer seconds since the epoch.
+    Seconds,
+    /// Milliseconds since the epoch with a fractional part.
+    FloatMillis,
+    /// Seconds since the epoch with a fractional part.
+    FloatSeconds,
+    /// An RFC 3339 string, such as `2025-01-31T12:00:00Z` or `2025-01-31T14:00:00.000+02:00`.
+    Rfc3339 {
+        /// Digits of fractional seconds; `None` writes as many as needed, in groups of three.
+        digits: Option<u8>,
+        /// Offset the time is written in; `None` writes UTC as `Z`.
+        offset: Option<FixedOffset>,
+    },
+    /// An ISO 8601 string without timezone, such as `2025-01-31T12:00:00`, in UTC.
+    NaiveIso {
+        /// The entry of `NAIVE_FORMATS` the string matched.
+        format: &'static str,
+        /// Digits of fractional seconds.
+        digits: u8,
+    },
+}
+
+impl TimestampForm {
+    /// RFC 3339 in UTC, with fractional seconds only when needed.
+    pub const RFC3339: TimestampForm = TimestampForm::Rfc3339 {
+        digits: None,
+        offset: None,
+    };
+
+    /// Returns a human-readable name for the form.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimestampForm::Millis => "Milliseconds",
+            TimestampForm::Seconds => "Seconds",
+            TimestampForm::FloatMillis => "Fractional milliseconds",
+            TimestampForm::FloatSeconds => "Fractional seconds",
+            TimestampForm::Rfc3339 { .. } => "RFC 3339",
+            TimestampForm::NaiveIso { .. } => "ISO without timezone",
+        }
+    }
+
+    /// Returns the forms offered when forcing one for every timestamp.
+    pub fn forcible() -> &'static [TimestampForm] {
+        &[
+            TimestampForm::Millis,
+            TimestampForm::Seconds,
+            TimestampForm::RFC3339,
+        ]
+    }
+}
+
+thread_local! {
+    /// Form every timestamp is serialized in, overriding the parsed one. See `serialize_as`.
+    static FORCED_FORM: Cell<Option<TimestampForm>> = const { Cell::new(None) };
+}
+
+/// Runs `f`, serializing every `T3Timestamp` on this thread in `form` instead of the form it
+/// was parsed from. `None` keeps the parsed forms.
+pub fn serialize_as<T>(form: Option<TimestampForm>, f: impl FnOnce() -> T) -> T {
+    /// Restores the previous form on drop, so a panic in `f` doesn't leave `form` forced.
+    struct Restore(Option<TimestampForm>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            FORCED_FORM.set(self.0);
+        }
+    }
+
+    let _restore = Restore(FORCED_FORM.replace(form));
+    f()
+}
+
+impl T3Timestamp {
+    /// Wraps a point in time read from somewhere without a JSON form, such as a database.
+    pub fn millis(date_time: DateTime<Utc>) -> Self {
+        T3Timestamp(date_time, TimestampForm::Millis)
+    }
+}
+
+impl PartialEq for T3Timestamp {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for T3Timestamp {}
+
+impl PartialOrd for T3Timestamp {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for T3Timestamp {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
 
 impl Deref for T3Timestamp {
     type Target = DateTime<Utc>;
@@ -25,10 +148,63 @@ impl Serialize for T3Timestamp {
     where
         S: Serializer,
     {
-        self.0.serialize(serializer)
+        let subsec_nanos = self.0.timestamp_subsec_nanos() as f64;
+        match FORCED_FORM.get().unwrap_or(self.1) {
+            TimestampForm::Millis => serializer.serialize_i64(self.0.timestamp_millis()),
+            TimestampForm::Seconds => serializer.serialize_i64(self.0.timestamp()),
+            TimestampForm::FloatMillis => {
+                serializer.serialize_f64(self.0.timestamp() as f64 * 1e3 + subsec_nanos / 1e6)
+            }
+            TimestampForm::FloatSeconds => {
+                serializer.serialize_f64(self.0.timestamp() as f64 + subsec_nanos / 1e9)
+            }
+            TimestampForm::Rfc3339 { digits, offset } => {
+                let fraction = fraction(self.0.timestamp_subsec_nanos(), digits);
+                serializer.serialize_str(&match offset {
+                    Some(offset) => {
+                        let local = self.0.with_timezone(&offset);
+                        format!(
+                            "{}{}{}",
+                            local.format("%Y-%m-%dT%H:%M:%S"),
+                            fraction,
+                            local.format("%:z")
+                        )
+                    }
+                    None => format!("{}{}Z", self.0.format("%Y-%m-%dT%H:%M:%S"), fraction),
+                })
+            }
+            TimestampForm::NaiveIso { format, digits } => serializer.serialize_str(&format!(
+                "{}{}",
+                self.0.naive_utc().format(format.trim_end_matches("%.f")),
+                fraction(self.0.timestamp_subsec_nanos(), Some(digits))
+            )),
+        }
     }
 }
 
+/// Formats fractional seconds with a leading dot, or nothing for zero digits.
+/// `None` picks 0, 3, 6 or 9 digits, whichever is enough.
+fn fraction(nanos: u32, digits: Option<u8>) -> String {
+    let digits = digits.unwrap_or(match nanos {
+        0 => 0,
+        n if n % 1_000_000 == 0 => 3,
+        n if n % 1_000 == 0 => 6,
+        _ => 9,
+    });
+    if digits == 0 {
+        return String::new();
+    }
+    let all = format!(".{:09}", nanos);
+    all[..all.len().min(digits as usize + 1)].to_string()
+}
+
+/// Counts the digits of fractional seconds in an ISO 8601 string.
+fn fraction_digits(value: &str) -> u8 {
+    value.split_once('.').map_or(0, |(_, rest)| {
+        rest.chars().take_while(char::is_ascii_digit).count() as u8
+    })
+}
+
 impl<'de> Deserialize<'de> for T3Timestamp {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -40,16 +216,21 @@ impl<'de> Deserialize<'de> for T3Timestamp {
             type Value = T3Timestamp;
 
             fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                formatter.write_str("an integer (timestamp in ms) or a string (RFC3339 date)")
+                formatter.write_str(ACCEPTED_FORMS)
             }
 
             fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
             where
                 E: serde::de::Error,
             {
-                match Utc.timestamp_millis_opt(value) {
-                    chrono::LocalResult::Single(dt) => Ok(T3Timestamp(dt)),
-                    _ => Err(E::custom(format!("invalid timestamp: {}", value))),
+                let (date_time, form) = if (value.unsigned_abs() as f64) < SECONDS_LIMIT {
+                    (Utc.timestamp_opt(value, 0), TimestampForm::Seconds)
+                } else {
+                    (Utc.timestamp_millis_opt(value), TimestampForm::Millis)
+                };
+                match date_time {
+                    chrono::LocalResult::Single(dt) => Ok(T3Timestamp(dt, form)),
+                    _ => Err(E::custom(format!("timestamp out of range: {}", value))),
                 }
             }
 
@@ -57,9 +238,36 @@ impl<'de> Deserialize<'de> for T3Timestamp {
             where
                 E: serde::de::Error,
             {
-                match Utc.timestamp_millis_opt(value as i64) {
-                    chrono::LocalResult::Single(dt) => Ok(T3Timestamp(dt)),
-                    _ => Err(E::custom(format!("invalid timestamp: {}", value))),
+                match i64::try_from(value) {
+                    Ok(value) => self.visit_i64(value),
+                    Err(_) => Err(E::custom(format!("timestamp out of range: {}", value))),
+                }
+            }
+
+            fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                let (nanos_per_unit, form) = if value.abs() < SECONDS_LIMIT {
+                    (1_000_000_000, TimestampForm::FloatSeconds)
+                } else {
+                    (1_000_000, TimestampForm::FloatMillis)
+                };
+                // Split off the fraction first, multiplying the whole value would lose precision
+                let whole = value.trunc();
+                let date_time = (value.is_finite() && whole.abs() < 1e15)
+                    .then(|| {
+                        let fraction = ((value - whole) * nanos_per_unit as f64).round() as i128;
+                        let nanos = whole as i128 * nanos_per_unit + fraction;
+                        DateTime::from_timestamp(
+                            nanos.div_euclid(1_000_000_000) as i64,
+                            nanos.rem_euclid(1_000_000_000) as u32,
+                        )
+                    })
+                    .flatten();
+                match date_time {
+                    Some(dt) => Ok(T3Timestamp(dt, form)),
+                    None => Err(E::custom(format!("timestamp out of range: {}", value))),
                 }
             }
 
@@ -67,13 +275,106 @@ impl<'de> Deserialize<'de> for T3Timestamp {
             where
                 E: serde::de::Error,
             {
-                match DateTime::parse_from_rfc3339(value) {
-                    Ok(dt) => Ok(T3Timestamp(dt.with_timezone(&Utc))),
-                    Err(e) => Err(E::custom(format!("invalid date string: {} ({})", value, e))),
+                let digits = fraction_digits(value);
+                if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+                    let form = TimestampForm::Rfc3339 {
+                        digits: Some(digits),
+                        offset: (!value.ends_with(['Z', 'z'])).then(|| *dt.offset()),
+                    };
+                    return Ok(T3Timestamp(dt.with_timezone(&Utc), form));
                 }
+                NAIVE_FORMATS
+                    .iter()
+                    .find_map(|format| {
+                        let naive = NaiveDateTime::parse_from_str(value, format).ok()?;
+                        Some(T3Timestamp(
+                            naive.and_utc(),
+                            TimestampForm::NaiveIso { format, digits },
+                        ))
+                    })
+                    .ok_or_else(|| {
+                        E::custom(format!(
+                            "invalid timestamp {:?}, expected {}",
+                            value, ACCEPTED_FORMS
+                        ))
+                    })
             }
         }
 
         deserializer.deserialize_any(T3TimestampVisitor)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::T3Timestamp;
+    use super::TimestampForm;
+    use super::serialize_as;
+
+    #[test]
+    fn serializes_in_the_parsed_form() {
+        let json = serde_json::json!([1735689600000i64, "2025-01-01T00:00:00Z"]);
+        let timestamps: Vec<T3Timestamp> = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(timestamps[0], timestamps[1]);
+        assert_eq!(serde_json::to_value(&timestamps).unwrap(), json);
+
+        let forced = serialize_as(Some(TimestampForm::Millis), || {
+            serde_json::to_value(&timestamps).unwrap()
+        });
+        assert_eq!(
+            forced,
+            serde_json::json!([1735689600000i64, 1735689600000i64])
+        );
+        // The override ends with the closure, even when it panics
+        assert_eq!(serde_json::to_value(&timestamps).unwrap(), json);
+        let panicked = std::panic::catch_unwind(|| {
+            serialize_as(Some(TimestampForm::Seconds), || {
+                panic!("failed to serialize")
+            })
+        });
+        assert!(panicked.is_err());
+        assert_eq!(serde_json::to_value(&timestamps).unwrap(), json);
+    }
+
+    #[test]
+    fn detects_epoch_units_and_naive_strings() {
+        let expected = serde_json::json!(1738324800000i64);
+        for json in [
+            serde_json::json!(1738324800),
+            serde_json::json!(1738324800000i64),
+            serde_json::json!(1738324800.0),
+            serde_json::json!(1738324800000.0),
+            serde_json::json!("2025-01-31T12:00:00"),
+            serde_json::json!("2025-01-31 12:00:00.000"),
+            serde_json::json!("2025-01-31T12:00:00.000Z"),
+            serde_json::json!("2025-01-31T14:00:00+02:00"),
+            serde_json::json!("2025-01-31T11:30:00.000000-00:30"),
+        ] {
+            let timestamp: T3Timestamp = serde_json::from_value(json.clone()).unwrap();
+            let millis = serialize_as(Some(TimestampForm::Millis), || {
+                serde_json::to_value(timestamp).unwrap()
+            });
+            assert_eq!(millis, expected, "parsing {}", json);
+            // Each form is written back the way it was read
+            assert_eq!(serde_json::to_value(timestamp).unwrap(), json);
+        }
+
+        let json = serde_json::json!("2025-01-31T12:00:00.25");
+        let naive: T3Timestamp = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(naive).unwrap(), json);
+        let rfc3339 = serialize_as(Some(TimestampForm::RFC3339), || {
+            serde_json::to_value(naive).unwrap()
+        });
+        assert_eq!(rfc3339, serde_json::json!("2025-01-31T12:00:00.250Z"));
+
+        let json = serde_json::json!(1738324800.25);
+        let fractional: T3Timestamp = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(fractional.timestamp_subsec_millis(), 250);
+        assert_eq!(serde_json::to_value(fractional).unwrap(), json);
+
+        let error = serde_json::from_value::<T3Timestamp>(serde_json::json!("yesterday"))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("RFC 3339"), "{}", error);
+    }
+}
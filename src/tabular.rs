@@ -0,0 +1,346 @@
+// src/tabular.rs
+
+use arrow_array::ArrayRef;
+use arrow_array::RecordBatch;
+use arrow_array::StringArray;
+use arrow_array::TimestampMillisecondArray;
+use arrow_array::UInt64Array;
+use arrow_schema::DataType;
+use arrow_schema::Field;
+use arrow_schema::Schema;
+use arrow_schema::TimeUnit;
+use chrono::DateTime;
+use chrono::SecondsFormat;
+use chrono::Utc;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::chart_export::ExportFile;
+use crate::filter::FilteredView;
+
+/// Columns of the threads table, in order. Adding columns is fine; renaming or reordering
+/// breaks downstream notebooks.
+pub const THREAD_COLUMNS: [&str; 8] = [
+    "id",
+    "title",
+    "status",
+    "model",
+    "created_at",
+    "updated_at",
+    "last_message_at",
+    "message_count",
+];
+
+/// Columns of the messages table, in order. `content_length` counts characters.
+pub const MESSAGE_COLUMNS: [&str; 8] = [
+    "id",
+    "thread_id",
+    "role",
+    "status",
+    "model",
+    "created_at",
+    "content_length",
+    "content",
+];
+
+/// File format for the tabular export.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TableFormat {
+    #[default]
+    Csv,
+    Parquet,
+}
+
+impl TableFormat {
+    /// Returns a human-readable name for the format.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TableFormat::Csv => "CSV",
+            TableFormat::Parquet => "Parquet",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TableFormat::Csv => "csv",
+            TableFormat::Parquet => "parquet",
+        }
+    }
+
+    pub fn all() -> &'static [TableFormat] {
+        &[TableFormat::Csv, TableFormat::Parquet]
+    }
+}
+
+struct ThreadRow<'a> {
+    id: &'a str,
+    title: &'a str,
+    status: &'static str,
+    model: &'a str,
+    created_at: DateTime<Utc>,
+    updated_at: Option<DateTime<Utc>>,
+    last_message_at: DateTime<Utc>,
+    message_count: u64,
+}
+
+struct MessageRow<'a> {
+    id: &'a str,
+    thread_id: &'a str,
+    role: &'static str,
+    status: &'static str,
+    model: &'a str,
+    created_at: DateTime<Utc>,
+    content_length: u64,
+    content: &'a str,
+}
+
+/// Writes the threads and messages of the view as `threads` and `messages` tables.
+pub fn export_tables(view: &FilteredView, format: TableFormat) -> eyre::Result<Vec<ExportFile>> {
+    let mut message_counts: HashMap<&str, u64> = HashMap::new();
+    for message in &view.messages {
+        *message_counts
+            .entry(message.thread_id.as_str())
+            .or_default() += 1;
+    }
+    let threads: Vec<ThreadRow> = view
+        .threads
+        .iter()
+        .map(|thread| ThreadRow {
+            id: &thread.id,
+            title: &thread.title,
+            status: thread.status.as_str(),
+            model: &thread.model,
+            created_at: *thread.created_at,
+            updated_at: thread.updated_at.map(|t| *t),
+            last_message_at: *thread.last_message_at,
+            message_count: message_counts.get(thread.id.as_str()).copied().unwrap_or(0),
+        })
+        .collect();
+    let messages: Vec<MessageRow> = view
+        .messages
+        .iter()
+        .map(|message| MessageRow {
+            id: &message.id,
+            thread_id: &message.thread_id,
+            role: message.role.as_str(),
+            status: message.status.as_str(),
+            model: &message.model,
+            created_at: *message.created_at,
+            content_length: message.content.chars().count() as u64,
+            content: &message.content,
+        })
+        .collect();
+
+    let (threads, messages) = match format {
+        TableFormat::Csv => (threads_csv(&threads)?, messages_csv(&messages)?),
+        TableFormat::Parquet => (threads_parquet(&threads)?, messages_parquet(&messages)?),
+    };
+    Ok(vec![
+        ExportFile {
+            file_name: format!("threads.{}", format.extension()),
+            bytes: threads,
+        },
+        ExportFile {
+            file_name: format!("messages.{}", format.extension()),
+            bytes: messages,
+        },
+    ])
+}
+
+/// Formats a timestamp for CSV, always with milliseconds so every row has the same shape.
+fn csv_timestamp(date_time: DateTime<Utc>) -> String {
+    date_time.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn threads_csv(rows: &[ThreadRow]) -> eyre::Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(THREAD_COLUMNS)?;
+    for row in rows {
+        writer.write_record([
+            row.id,
+            row.title,
+            row.status,
+            row.model,
+            &csv_timestamp(row.created_at),
+            &row.updated_at.map(csv_timestamp).unwrap_or_default(),
+            &csv_timestamp(row.last_message_at),
+            &row.message_count.to_string(),
+        ])?;
+    }
+    Ok(writer.into_inner()?)
+}
+
+fn messages_csv(rows: &[MessageRow]) -> eyre::Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(MESSAGE_COLUMNS)?;
+    for row in rows {
+        writer.write_record([
+            row.id,
+            row.thread_id,
+            row.role,
+            row.status,
+            row.model,
+            &csv_timestamp(row.created_at),
+            &row.content_length.to_string(),
+            row.content,
+        ])?;
+    }
+    Ok(writer.into_inner()?)
+}
+
+fn timestamp_type() -> DataType {
+    DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into()))
+}
+
+fn strings<'a>(values: impl Iterator<Item = &'a str>) -> ArrayRef {
+    Arc::new(StringArray::from_iter_values(values))
+}
+
+fn timestamps(values: impl Iterator<Item = Option<DateTime<Utc>>>) -> ArrayRef {
+    Arc::new(
+        values
+            .map(|value| value.map(|t| t.timestamp_millis()))
+            .collect::<TimestampMillisecondArray>()
+            .with_timezone("UTC"),
+    )
+}
+
+fn threads_parquet(rows: &[ThreadRow]) -> eyre::Result<Vec<u8>> {
+    let types = [
+        (DataType::Utf8, false),
+        (DataType::Utf8, false),
+        (DataType::Utf8, false),
+        (DataType::Utf8, false),
+        (timestamp_type(), false),
+        (timestamp_type(), true),
+        (timestamp_type(), false),
+        (DataType::UInt64, false),
+    ];
+    let columns: Vec<ArrayRef> = vec![
+        strings(rows.iter().map(|row| row.id)),
+        strings(rows.iter().map(|row| row.title)),
+        strings(rows.iter().map(|row| row.status)),
+        strings(rows.iter().map(|row| row.model)),
+        timestamps(rows.iter().map(|row| Some(row.created_at))),
+        timestamps(rows.iter().map(|row| row.updated_at)),
+        timestamps(rows.iter().map(|row| Some(row.last_message_at))),
+        Arc::new(UInt64Array::from_iter_values(
+            rows.iter().map(|row| row.message_count),
+        )),
+    ];
+    write_parquet(&THREAD_COLUMNS, types, columns)
+}
+
+fn messages_parquet(rows: &[MessageRow]) -> eyre::Result<Vec<u8>> {
+    let types = [
+        (DataType::Utf8, false),
+        (DataType::Utf8, false),
+        (DataType::Utf8, false),
+        (DataType::Utf8, false),
+        (DataType::Utf8, false),
+        (timestamp_type(), false),
+        (DataType::UInt64, false),
+        (DataType::Utf8, false),
+    ];
+    let columns: Vec<ArrayRef> = vec![
+        strings(rows.iter().map(|row| row.id)),
+        strings(rows.iter().map(|row| row.thread_id)),
+        strings(rows.iter().map(|row| row.role)),
+        strings(rows.iter().map(|row| row.status)),
+        strings(rows.iter().map(|row| row.model)),
+        timestamps(rows.iter().map(|row| Some(row.created_at))),
+        Arc::new(UInt64Array::from_iter_values(
+            rows.iter().map(|row| row.content_length),
+        )),
+        strings(rows.iter().map(|row| row.content)),
+    ];
+    write_parquet(&MESSAGE_COLUMNS, types, columns)
+}
+
+/// Writes one record batch with the given column names and `(type, nullable)` pairs.
+fn write_parquet<const N: usize>(
+    names: &[&str; N],
+    types: [(DataType, bool); N],
+    columns: Vec<ArrayRef>,
+) -> eyre::Result<Vec<u8>> {
+    let fields: Vec<Field> = names
+        .iter()
+        .zip(types)
+        .map(|(name, (data_type, nullable))| Field::new(*name, data_type, nullable))
+        .collect();
+    let schema = Arc::new(Schema::new(fields));
+    let batch = RecordBatch::try_new(schema.clone(), columns)?;
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut bytes = vec![];
+    let mut writer = ArrowWriter::try_new(&mut bytes, schema, Some(properties))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::MESSAGE_COLUMNS;
+    use super::TableFormat;
+    use super::export_tables;
+    use crate::filter::ThreadFilter;
+    use crate::t3_json::T3Json;
+    use crate::t3_json::fixture;
+    use parquet::file::reader::FileReader;
+    use parquet::file::reader::SerializedFileReader;
+
+    fn export() -> T3Json {
+        fixture::export(
+            [],
+            [fixture::message("a", "t1")
+                .content("héllo, world")
+                .role("assistant")
+                .status("error")],
+        )
+    }
+
+    #[test]
+    fn csv_has_stable_columns() -> eyre::Result<()> {
+        let t3_json = export();
+        let files = export_tables(&ThreadFilter::default().apply(&t3_json), TableFormat::Csv)?;
+        assert_eq!(files[0].file_name, "threads.csv");
+        assert!(String::from_utf8(files[0].bytes.clone())?.starts_with("id,title,status,"));
+        let messages = String::from_utf8(files[1].bytes.clone())?;
+        let mut lines = messages.lines();
+        assert_eq!(lines.next(), Some(MESSAGE_COLUMNS.join(",").as_str()));
+        assert_eq!(
+            lines.next(),
+            Some("a,t1,assistant,error,m1,2025-01-01T00:00:00.000Z,12,\"héllo, world\"")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parquet_has_stable_columns() -> eyre::Result<()> {
+        let t3_json = export();
+        let files = export_tables(
+            &ThreadFilter::default().apply(&t3_json),
+            TableFormat::Parquet,
+        )?;
+        let path = std::env::temp_dir().join(format!("t3-messages-{}.parquet", std::process::id()));
+        std::fs::write(&path, &files[1].bytes)?;
+        let reader = SerializedFileReader::new(std::fs::File::open(&path)?)?;
+        std::fs::remove_file(&path)?;
+        let metadata = reader.metadata();
+        assert_eq!(metadata.file_metadata().num_rows(), 1);
+        let names: Vec<&str> = metadata
+            .file_metadata()
+            .schema_descr()
+            .columns()
+            .iter()
+            .map(|column| column.name())
+            .collect();
+        assert_eq!(names, MESSAGE_COLUMNS);
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+// src/time_bucket.rs
+
+use chrono::Datelike;
+use chrono::Duration;
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use chrono::NaiveTime;
+use chrono::Timelike;
+
+use crate::t3_json::T3Message;
+use crate::timezone::DisplayTimezone;
+
+/// The width of the buckets messages are counted in for time series charts.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum TimeBucket {
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl TimeBucket {
+    /// Returns a human-readable name for the bucket size.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimeBucket::Hour => "Hour",
+            TimeBucket::Day => "Day",
+            TimeBucket::Week => "Week",
+            TimeBucket::Month => "Month",
+            TimeBucket::Year => "Year",
+        }
+    }
+
+    /// Returns all available bucket sizes.
+    pub fn all() -> &'static [TimeBucket] {
+        &[
+            TimeBucket::Hour,
+            TimeBucket::Day,
+            TimeBucket::Week,
+            TimeBucket::Month,
+            TimeBucket::Year,
+        ]
+    }
+
+    /// Returns the `strftime` format used to label a bucket start.
+    pub fn label_format(&self) -> &'static str {
+        match self {
+            TimeBucket::Hour => "%Y-%m-%d %H:00",
+            TimeBucket::Day | TimeBucket::Week => "%Y-%m-%d",
+            TimeBucket::Month => "%Y-%m",
+            TimeBucket::Year => "%Y",
+        }
+    }
+
+    /// Returns the start of the bucket containing `date_time`.
+    /// Weeks start on Monday.
+    pub fn floor(&self, date_time: NaiveDateTime) -> NaiveDateTime {
+        let date = date_time.date();
+        match self {
+            TimeBucket::Hour => date
+                .and_hms_opt(date_time.hour(), 0, 0)
+                .unwrap_or(date_time),
+            TimeBucket::Day => date.and_time(NaiveTime::MIN),
+            TimeBucket::Week => {
+                let monday = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+                monday.and_time(NaiveTime::MIN)
+            }
+            TimeBucket::Month => date.with_day(1).unwrap_or(date).and_time(NaiveTime::MIN),
+            TimeBucket::Year => NaiveDate::from_ymd_opt(date.year(), 1, 1)
+                .unwrap_or(date)
+                .and_time(NaiveTime::MIN),
+        }
+    }
+
+    /// Returns the start of the bucket after the one containing `date_time`.
+    pub fn next(&self, date_time: NaiveDateTime) -> NaiveDateTime {
+        let start = self.floor(date_time);
+        match self {
+            TimeBucket::Hour => start + Duration::hours(1),
+            TimeBucket::Day => start + Duration::days(1),
+            TimeBucket::Week => start + Duration::weeks(1),
+            TimeBucket::Month => {
+                let date = start.date();
+                let next = if date.month() == 12 {
+                    NaiveDate::from_ymd_opt(date.year() + 1, 1, 1)
+                } else {
+                    NaiveDate::from_ymd_opt(date.year(), date.month() + 1, 1)
+                };
+                next.unwrap_or(date).and_time(NaiveTime::MIN)
+            }
+            TimeBucket::Year => NaiveDate::from_ymd_opt(start.year() + 1, 1, 1)
+                .unwrap_or(start.date())
+                .and_time(NaiveTime::MIN),
+        }
+    }
+
+    /// Returns the start of every bucket overlapping the range, in order.
+    pub fn starts(&self, range: &DateRange) -> Vec<NaiveDateTime> {
+        let end = range.end.and_time(NaiveTime::MIN) + Duration::days(1);
+        let mut starts = vec![];
+        let mut start = self.floor(range.start.and_time(NaiveTime::MIN));
+        while start < end {
+            starts.push(start);
+            start = self.next(start);
+        }
+        starts
+    }
+}
+
+/// An inclusive range of calendar days.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct DateRange {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl DateRange {
+    /// Returns the range from the first to the last message, as days in the given timezone,
+    /// or `None` if there are no messages.
+    pub fn of_messages(messages: &[&T3Message], timezone: DisplayTimezone) -> Option<Self> {
+        let start = messages.iter().map(|m| m.created_at).min()?;
+        let end = messages.iter().map(|m| m.created_at).max()?;
+        let start = timezone.naive(*start).date();
+        let end = timezone.naive(*end).date();
+        Some(Self { start, end })
+    }
+
+    /// Returns true when the day is within the range.
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.start <= date && date <= self.end
+    }
+}
+
+/// Counts messages per bucket over the range, using wall-clock time in the given timezone.
+/// Every bucket in the range is returned, including empty ones, ordered by bucket start.
+pub fn bucket_counts(
+    messages: &[&T3Message],
+    range: &DateRange,
+    bucket: TimeBucket,
+    timezone: DisplayTimezone,
+) -> Vec<(NaiveDateTime, usize)> {
+    let mut counts: Vec<(NaiveDateTime, usize)> = bucket
+        .starts(range)
+        .into_iter()
+        .map(|start| (start, 0))
+        .collect();
+    for message in messages {
+        let date_time = timezone.naive(*message.created_at);
+        if !range.contains(date_time.date()) {
+            continue;
+        }
+        let start = bucket.floor(date_time);
+        if let Ok(index) = counts.binary_search_by_key(&start, |(start, _)| *start) {
+            counts[index].1 += 1;
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod test {
+    use super::DateRange;
+    use super::TimeBucket;
+    use chrono::NaiveDate;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        // 2025-01-01 was a Wednesday
+        let start = TimeBucket::Week.floor(date(2025, 1, 1).and_hms_opt(13, 0, 0).unwrap());
+        assert_eq!(start.date(), date(2024, 12, 30));
+    }
+
+    #[test]
+    fn months_roll_over_the_year() {
+        let next = TimeBucket::Month.next(date(2024, 12, 15).and_hms_opt(0, 0, 0).unwrap());
+        assert_eq!(next.date(), date(2025, 1, 1));
+    }
+
+    #[test]
+    fn starts_cover_the_whole_range() {
+        let range = DateRange {
+            start: date(2024, 11, 20),
+            end: date(2025, 2, 1),
+        };
+        let starts: Vec<NaiveDate> = TimeBucket::Month
+            .starts(&range)
+            .into_iter()
+            .map(|start| start.date())
+            .collect();
+        assert_eq!(
+            starts,
+            vec![
+                date(2024, 11, 1),
+                date(2024, 12, 1),
+                date(2025, 1, 1),
+                date(2025, 2, 1)
+            ]
+        );
+        assert_eq!(TimeBucket::Hour.starts(&range).len(), 74 * 24);
+    }
+}
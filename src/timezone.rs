@@ -0,0 +1,75 @@
+// src/timezone.rs
+
+use chrono::DateTime;
+use chrono::Local;
+use chrono::NaiveDateTime;
+use chrono::Utc;
+use chrono_tz::Tz;
+use eframe::egui;
+use std::str::FromStr;
+
+/// The timezone timestamps are displayed and bucketed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DisplayTimezone {
+    /// The timezone of the machine running the viewer.
+    #[default]
+    Local,
+    /// An IANA timezone such as `America/Vancouver` or `UTC`.
+    Named(Tz),
+}
+
+impl FromStr for DisplayTimezone {
+    type Err = String;
+
+    /// Parses `local` or an IANA timezone name.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("local") {
+            return Ok(DisplayTimezone::Local);
+        }
+        s.parse::<Tz>()
+            .map(DisplayTimezone::Named)
+            .map_err(|e| format!("unknown timezone {:?}: {}", s, e))
+    }
+}
+
+impl DisplayTimezone {
+    /// Returns a human-readable name for the timezone.
+    pub fn name(&self) -> String {
+        match self {
+            DisplayTimezone::Local => "System local".to_string(),
+            DisplayTimezone::Named(tz) => tz.name().to_string(),
+        }
+    }
+
+    /// Converts a UTC instant to the wall-clock time in this timezone.
+    pub fn naive(&self, date_time: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            DisplayTimezone::Local => date_time.with_timezone(&Local).naive_local(),
+            DisplayTimezone::Named(tz) => date_time.with_timezone(tz).naive_local(),
+        }
+    }
+
+    /// Formats a UTC instant in this timezone, including the offset.
+    pub fn format(&self, date_time: DateTime<Utc>) -> String {
+        const FORMAT: &str = "%Y-%m-%d %H:%M:%S %:z";
+        match self {
+            DisplayTimezone::Local => date_time.with_timezone(&Local).format(FORMAT).to_string(),
+            DisplayTimezone::Named(tz) => date_time.with_timezone(tz).format(FORMAT).to_string(),
+        }
+    }
+
+    /// Draws a combo box to pick the system timezone or any IANA timezone.
+    pub fn draw_picker(&mut self, ui: &mut egui::Ui) {
+        egui::ComboBox::from_id_salt("display_timezone")
+            .selected_text(self.name())
+            .height(400.0)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(self, DisplayTimezone::Local, "System local");
+                ui.selectable_value(self, DisplayTimezone::Named(Tz::UTC), "UTC");
+                ui.separator();
+                for tz in chrono_tz::TZ_VARIANTS {
+                    ui.selectable_value(self, DisplayTimezone::Named(tz), tz.name());
+                }
+            });
+    }
+}